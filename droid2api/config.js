@@ -42,6 +42,11 @@ export function isDevMode() {
 }
 
 export function getPort() {
+  // DROID2API_PORT is passed by cc-switch only when a port is chosen; it takes precedence over config.json
+  const envPort = parseInt(process.env.DROID2API_PORT, 10);
+  if (envPort > 0) {
+    return envPort;
+  }
   const cfg = getConfig();
   return cfg.port || 3000;
 }
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
 
 /// MCP 配置：单客户端维度（claude 或 codex 下的一组服务器）
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -9,6 +10,56 @@ pub struct McpConfig {
     pub servers: HashMap<String, serde_json::Value>,
 }
 
+impl McpConfig {
+    /// 设置服务器的启用状态（仅修改 enabled 字段，保留其余定义）
+    pub fn set_server_enabled(&mut self, id: &str, enabled: bool) -> Result<(), String> {
+        let obj = self
+            .servers
+            .get_mut(id)
+            .ok_or_else(|| format!("MCP 服务器不存在: {}", id))?
+            .as_object_mut()
+            .ok_or_else(|| "MCP 服务器定义必须为 JSON 对象".to_string())?;
+        obj.insert("enabled".into(), serde_json::Value::Bool(enabled));
+        Ok(())
+    }
+
+    /// 已启用的服务器 id（按 id 排序）
+    pub fn enabled_servers(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self
+            .servers
+            .iter()
+            .filter(|(_, entry)| {
+                entry
+                    .get("enabled")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false)
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    /// 校验所有服务器定义，返回每个无效条目的错误信息（按 id 排序）
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut ids: Vec<&String> = self.servers.keys().collect();
+        ids.sort();
+        let errors: Vec<String> = ids
+            .into_iter()
+            .filter_map(|id| {
+                crate::mcp::validate_mcp_entry_strict(&self.servers[id])
+                    .err()
+                    .map(|e| format!("MCP 条目 '{}' 无效: {}", id, e))
+            })
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
 /// MCP 根：按客户端分开维护（无历史兼容压力，直接以 v2 结构落地）
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct McpRoot {
@@ -16,9 +67,14 @@ pub struct McpRoot {
     pub claude: McpConfig,
     #[serde(default)]
     pub codex: McpConfig,
+    #[serde(default)]
+    pub gemini: McpConfig,
 }
 
-use crate::config::{copy_file, get_app_config_dir, get_app_config_path, write_json_file};
+use crate::config::{
+    copy_file, get_app_config_dir, get_app_config_path, get_profiles_dir, read_json_file,
+    write_json_file,
+};
 use crate::provider::ProviderManager;
 
 /// 应用类型
@@ -27,6 +83,7 @@ use crate::provider::ProviderManager;
 pub enum AppType {
     Claude,
     Codex,
+    Gemini,
 }
 
 impl AppType {
@@ -34,15 +91,20 @@ impl AppType {
         match self {
             AppType::Claude => "claude",
             AppType::Codex => "codex",
+            AppType::Gemini => "gemini",
         }
     }
 }
 
-impl From<&str> for AppType {
-    fn from(s: &str) -> Self {
-        match s.to_lowercase().as_str() {
-            "codex" => AppType::Codex,
-            _ => AppType::Claude, // 默认为 Claude
+impl std::str::FromStr for AppType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "claude" => Ok(AppType::Claude),
+            "codex" => Ok(AppType::Codex),
+            "gemini" => Ok(AppType::Gemini),
+            other => Err(format!("未知的应用类型: {}", other)),
         }
     }
 }
@@ -67,11 +129,24 @@ fn default_version() -> u32 {
     2
 }
 
+/// 完整配置导出文件的格式版本
+pub const EXPORT_FORMAT_VERSION: u32 = 1;
+
+/// 完整配置导出文件（apps/mcp/droid_manager 打包为单个 JSON）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConfigBundle {
+    format_version: u32,
+    exported_at: i64,
+    config: MultiAppConfig,
+}
+
 impl Default for MultiAppConfig {
     fn default() -> Self {
         let mut apps = HashMap::new();
         apps.insert("claude".to_string(), ProviderManager::default());
         apps.insert("codex".to_string(), ProviderManager::default());
+        apps.insert("gemini".to_string(), ProviderManager::default());
 
         Self {
             version: 2,
@@ -93,11 +168,12 @@ impl MultiAppConfig {
         }
 
         // 尝试读取文件
-        let content = std::fs::read_to_string(&config_path)
+        let raw = std::fs::read_to_string(&config_path)
             .map_err(|e| format!("读取配置文件失败: {}", e))?;
+        let content = crate::config::strip_bom(&raw);
 
         // 检查是否是旧版本格式（v1）
-        if let Ok(v1_config) = serde_json::from_str::<ProviderManager>(&content) {
+        if let Ok(v1_config) = serde_json::from_str::<ProviderManager>(content) {
             log::info!("检测到v1配置，自动迁移到v2");
 
             // 迁移到新格式
@@ -135,7 +211,21 @@ impl MultiAppConfig {
         }
 
         // 尝试读取v2格式
-        serde_json::from_str::<Self>(&content).map_err(|e| format!("解析配置文件失败: {}", e))
+        let mut config = serde_json::from_str::<Self>(content)
+            .map_err(|e| format!("解析配置文件失败: {}", e))?;
+
+        // 修复指向已删除 Provider 的 Droid 当前项
+        let repaired = config
+            .droid_manager
+            .as_mut()
+            .map(|manager| manager.repair_current())
+            .unwrap_or(false);
+        if repaired {
+            if let Err(e) = config.save() {
+                log::warn!("保存修复后的配置失败: {}", e);
+            }
+        }
+        Ok(config)
     }
 
     /// 保存配置到文件
@@ -153,6 +243,88 @@ impl MultiAppConfig {
         Ok(())
     }
 
+    /// 校验配置方案名称并返回其文件路径
+    fn profile_path(name: &str) -> Result<std::path::PathBuf, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("配置方案名称不能为空".to_string());
+        }
+        if name.starts_with('.')
+            || name
+                .chars()
+                .any(|c| matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|'))
+        {
+            return Err(format!("配置方案名称包含非法字符: {}", name));
+        }
+        Ok(get_profiles_dir().join(format!("{}.json", name)))
+    }
+
+    /// 将当前配置保存为命名配置方案（~/.cc-switch/profiles/<name>.json）
+    pub fn save_profile(&self, name: &str) -> Result<(), String> {
+        let path = Self::profile_path(name)?;
+        write_json_file(&path, self)
+    }
+
+    /// 读取命名配置方案（不切换当前配置）
+    pub fn load_profile(name: &str) -> Result<Self, String> {
+        let path = Self::profile_path(name)?;
+        if !path.exists() {
+            return Err(format!("配置方案不存在: {}", name.trim()));
+        }
+        read_json_file(&path)
+    }
+
+    /// 列出所有已保存的配置方案名称（按名称排序）
+    pub fn list_profiles() -> Result<Vec<String>, String> {
+        let dir = get_profiles_dir();
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        let entries =
+            std::fs::read_dir(&dir).map_err(|e| format!("读取配置方案目录失败: {}", e))?;
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().map(|ext| ext == "json").unwrap_or(false))
+            .filter_map(|path| path.file_stem().map(|s| s.to_string_lossy().to_string()))
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    /// 导出完整配置到指定文件（带格式版本，便于迁移到其他机器）
+    pub fn export_to(&self, path: &Path) -> Result<(), String> {
+        let bundle = ConfigBundle {
+            format_version: EXPORT_FORMAT_VERSION,
+            exported_at: chrono::Utc::now().timestamp(),
+            config: self.clone(),
+        };
+        write_json_file(path, &bundle)
+    }
+
+    /// 从导出文件读取完整配置（校验格式版本，不修改当前配置）
+    pub fn import_from(path: &Path) -> Result<Self, String> {
+        let raw = std::fs::read_to_string(path)
+            .map_err(|e| format!("读取导入文件失败: {}", e))?;
+        let value: serde_json::Value = serde_json::from_str(crate::config::strip_bom(&raw))
+            .map_err(|e| format!("解析导入文件失败: {}", e))?;
+
+        let format_version = value
+            .get("formatVersion")
+            .and_then(|v| v.as_u64())
+            .ok_or_else(|| "导入文件缺少 formatVersion，不是有效的配置导出文件".to_string())?;
+        if format_version > EXPORT_FORMAT_VERSION as u64 {
+            return Err(format!(
+                "导入文件格式版本 {} 高于当前支持的版本 {}，请先升级应用",
+                format_version, EXPORT_FORMAT_VERSION
+            ));
+        }
+
+        let bundle: ConfigBundle =
+            serde_json::from_value(value).map_err(|e| format!("解析导入文件失败: {}", e))?;
+        Ok(bundle.config)
+    }
+
     /// 获取指定应用的管理器
     pub fn get_manager(&self, app: &AppType) -> Option<&ProviderManager> {
         self.apps.get(app.as_str())
@@ -176,6 +348,7 @@ impl MultiAppConfig {
         match app {
             AppType::Claude => &self.mcp.claude,
             AppType::Codex => &self.mcp.codex,
+            AppType::Gemini => &self.mcp.gemini,
         }
     }
 
@@ -184,6 +357,7 @@ impl MultiAppConfig {
         match app {
             AppType::Claude => &mut self.mcp.claude,
             AppType::Codex => &mut self.mcp.codex,
+            AppType::Gemini => &mut self.mcp.gemini,
         }
     }
 }
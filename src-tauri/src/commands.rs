@@ -14,6 +14,121 @@ use crate::provider::{Provider, ProviderMeta};
 use crate::speedtest;
 use crate::store::AppState;
 
+/// 解析前端传入的应用类型参数（均未指定时默认为 Claude，无法识别时报错）
+fn resolve_app_type(
+    app_type: Option<AppType>,
+    app: Option<String>,
+    app_type_str: Option<String>,
+) -> Result<AppType, String> {
+    if let Some(app_type) = app_type {
+        return Ok(app_type);
+    }
+    match app.or(app_type_str) {
+        Some(s) => s.parse(),
+        None => Ok(AppType::Claude),
+    }
+}
+
+/// Claude / Gemini 的 live 配置均为单个 settings.json
+fn json_settings_path(app_type: &AppType) -> std::path::PathBuf {
+    match app_type {
+        AppType::Gemini => crate::config::get_gemini_settings_path(),
+        _ => get_claude_settings_path(),
+    }
+}
+
+/// 将供应商的 settings_config 写入对应应用的 live 配置文件
+fn write_live_settings(app_type: &AppType, provider: &Provider) -> Result<(), String> {
+    match app_type {
+        AppType::Claude | AppType::Gemini => {
+            let settings_path = json_settings_path(app_type);
+            if let Some(parent) = settings_path.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+            }
+            crate::config::write_json_file(&settings_path, &provider.settings_config)
+        }
+        AppType::Codex => {
+            let auth = provider
+                .settings_config
+                .get("auth")
+                .ok_or_else(|| "目标供应商缺少 auth 配置".to_string())?;
+            let cfg_text = provider
+                .settings_config
+                .get("config")
+                .and_then(|v| v.as_str());
+            crate::codex_config::write_codex_live_atomic(auth, cfg_text)
+        }
+    }
+}
+
+/// 读取应用当前的 live 配置（格式同 settings_config），不存在或无法解析时返回 None
+fn read_live_settings(app_type: &AppType) -> Result<Option<serde_json::Value>, String> {
+    match app_type {
+        AppType::Claude | AppType::Gemini => {
+            let settings_path = json_settings_path(app_type);
+            if !settings_path.exists() {
+                return Ok(None);
+            }
+            Ok(crate::config::read_json_file::<serde_json::Value>(&settings_path).ok())
+        }
+        AppType::Codex => {
+            let auth_path = codex_config::get_codex_auth_path();
+            if !auth_path.exists() {
+                return Ok(None);
+            }
+            let auth: serde_json::Value = crate::config::read_json_file(&auth_path)?;
+            let config_path = codex_config::get_codex_config_path();
+            let config_str = if config_path.exists() {
+                std::fs::read_to_string(&config_path).map_err(|e| {
+                    format!("读取 config.toml 失败: {}: {}", config_path.display(), e)
+                })?
+            } else {
+                String::new()
+            };
+            Ok(Some(serde_json::json!({
+                "auth": auth,
+                "config": config_str,
+            })))
+        }
+    }
+}
+
+/// 文件路径及其内容（不存在时为 None）
+type FileSnapshot = Vec<(std::path::PathBuf, Option<Vec<u8>>)>;
+
+/// 读取文件当前内容，用于失败时回滚
+fn snapshot_files(paths: Vec<std::path::PathBuf>) -> Result<FileSnapshot, String> {
+    paths
+        .into_iter()
+        .map(|path| {
+            let content = if path.exists() {
+                Some(
+                    std::fs::read(&path)
+                        .map_err(|e| format!("读取文件失败: {}: {}", path.display(), e))?,
+                )
+            } else {
+                None
+            };
+            Ok((path, content))
+        })
+        .collect()
+}
+
+/// 将文件恢复为快照时的内容（原本不存在的文件会被删除）
+fn restore_files(snapshot: FileSnapshot) {
+    for (path, content) in snapshot {
+        let result = match content {
+            Some(bytes) => crate::config::atomic_write(&path, &bytes),
+            None if path.exists() => std::fs::remove_file(&path)
+                .map_err(|e| format!("删除文件失败: {}: {}", path.display(), e)),
+            None => Ok(()),
+        };
+        if let Err(e) = result {
+            log::warn!("回滚 {} 失败: {}", path.display(), e);
+        }
+    }
+}
+
 fn validate_provider_settings(app_type: &AppType, provider: &Provider) -> Result<(), String> {
     match app_type {
         AppType::Claude => {
@@ -21,6 +136,11 @@ fn validate_provider_settings(app_type: &AppType, provider: &Provider) -> Result
                 return Err("Claude 配置必须是 JSON 对象".to_string());
             }
         }
+        AppType::Gemini => {
+            if !provider.settings_config.is_object() {
+                return Err("Gemini 配置必须是 JSON 对象".to_string());
+            }
+        }
         AppType::Codex => {
             let settings = provider
                 .settings_config
@@ -53,10 +173,7 @@ pub async fn get_providers(
     app: Option<String>,
     appType: Option<String>,
 ) -> Result<HashMap<String, Provider>, String> {
-    let app_type = app_type
-        .or_else(|| app.as_deref().map(|s| s.into()))
-        .or_else(|| appType.as_deref().map(|s| s.into()))
-        .unwrap_or(AppType::Claude);
+    let app_type = resolve_app_type(app_type, app, appType)?;
 
     let config = state
         .config
@@ -78,10 +195,7 @@ pub async fn get_current_provider(
     app: Option<String>,
     appType: Option<String>,
 ) -> Result<String, String> {
-    let app_type = app_type
-        .or_else(|| app.as_deref().map(|s| s.into()))
-        .or_else(|| appType.as_deref().map(|s| s.into()))
-        .unwrap_or(AppType::Claude);
+    let app_type = resolve_app_type(app_type, app, appType)?;
 
     let config = state
         .config
@@ -104,10 +218,7 @@ pub async fn add_provider(
     appType: Option<String>,
     provider: Provider,
 ) -> Result<bool, String> {
-    let app_type = app_type
-        .or_else(|| app.as_deref().map(|s| s.into()))
-        .or_else(|| appType.as_deref().map(|s| s.into()))
-        .unwrap_or(AppType::Claude);
+    let app_type = resolve_app_type(app_type, app, appType)?;
 
     validate_provider_settings(&app_type, &provider)?;
 
@@ -125,23 +236,7 @@ pub async fn add_provider(
 
     // 若目标为当前供应商，则先写 live，成功后再落盘配置
     if is_current {
-        match app_type {
-            AppType::Claude => {
-                let settings_path = crate::config::get_claude_settings_path();
-                crate::config::write_json_file(&settings_path, &provider.settings_config)?;
-            }
-            AppType::Codex => {
-                let auth = provider
-                    .settings_config
-                    .get("auth")
-                    .ok_or_else(|| "目标供应商缺少 auth 配置".to_string())?;
-                let cfg_text = provider
-                    .settings_config
-                    .get("config")
-                    .and_then(|v| v.as_str());
-                crate::codex_config::write_codex_live_atomic(auth, cfg_text)?;
-            }
-        }
+        write_live_settings(&app_type, &provider)?;
     }
 
     // 更新内存并保存配置
@@ -171,10 +266,7 @@ pub async fn update_provider(
     appType: Option<String>,
     provider: Provider,
 ) -> Result<bool, String> {
-    let app_type = app_type
-        .or_else(|| app.as_deref().map(|s| s.into()))
-        .or_else(|| appType.as_deref().map(|s| s.into()))
-        .unwrap_or(AppType::Claude);
+    let app_type = resolve_app_type(app_type, app, appType)?;
 
     validate_provider_settings(&app_type, &provider)?;
 
@@ -198,23 +290,7 @@ pub async fn update_provider(
 
     // 若更新的是当前供应商，先写 live 成功再保存
     if is_current {
-        match app_type {
-            AppType::Claude => {
-                let settings_path = crate::config::get_claude_settings_path();
-                crate::config::write_json_file(&settings_path, &provider.settings_config)?;
-            }
-            AppType::Codex => {
-                let auth = provider
-                    .settings_config
-                    .get("auth")
-                    .ok_or_else(|| "目标供应商缺少 auth 配置".to_string())?;
-                let cfg_text = provider
-                    .settings_config
-                    .get("config")
-                    .and_then(|v| v.as_str());
-                crate::codex_config::write_codex_live_atomic(auth, cfg_text)?;
-            }
-        }
+        write_live_settings(&app_type, &provider)?;
     }
 
     // 更新内存并保存（保留/合并已有的 meta.custom_endpoints，避免丢失在编辑流程中新增的自定义端点）
@@ -278,10 +354,7 @@ pub async fn delete_provider(
     appType: Option<String>,
     id: String,
 ) -> Result<bool, String> {
-    let app_type = app_type
-        .or_else(|| app.as_deref().map(|s| s.into()))
-        .or_else(|| appType.as_deref().map(|s| s.into()))
-        .unwrap_or(AppType::Claude);
+    let app_type = resolve_app_type(app_type, app, appType)?;
 
     let mut config = state
         .config
@@ -317,6 +390,9 @@ pub async fn delete_provider(
             delete_file(&by_name)?;
             delete_file(&by_id)?;
         }
+        AppType::Gemini => {
+            // Gemini 不生成供应商副本文件
+        }
     }
 
     // 从管理器删除
@@ -338,10 +414,7 @@ pub async fn switch_provider(
     appType: Option<String>,
     id: String,
 ) -> Result<bool, String> {
-    let app_type = app_type
-        .or_else(|| app.as_deref().map(|s| s.into()))
-        .or_else(|| appType.as_deref().map(|s| s.into()))
-        .unwrap_or(AppType::Claude);
+    let app_type = resolve_app_type(app_type, app, appType)?;
 
     let mut config = state
         .config
@@ -418,10 +491,10 @@ pub async fn switch_provider(
                 .and_then(|v| v.as_str());
             crate::codex_config::write_codex_live_atomic(auth, cfg_text)?;
         }
-        AppType::Claude => {
+        AppType::Claude | AppType::Gemini => {
             use crate::config::{read_json_file, write_json_file};
 
-            let settings_path = get_claude_settings_path();
+            let settings_path = json_settings_path(&app_type);
 
             // 回填：读取 live settings.json 写回当前供应商 settings_config
             if settings_path.exists() {
@@ -518,10 +591,7 @@ pub async fn import_default_config(
     app: Option<String>,
     appType: Option<String>,
 ) -> Result<bool, String> {
-    let app_type = app_type
-        .or_else(|| app.as_deref().map(|s| s.into()))
-        .or_else(|| appType.as_deref().map(|s| s.into()))
-        .unwrap_or(AppType::Claude);
+    let app_type = resolve_app_type(app_type, app, appType)?;
 
     // 仅当 providers 为空时才从 live 导入一条默认项
     {
@@ -560,6 +630,13 @@ pub async fn import_default_config(
             }
             crate::config::read_json_file::<serde_json::Value>(&settings_path)?
         }
+        AppType::Gemini => {
+            let settings_path = crate::config::get_gemini_settings_path();
+            if !settings_path.exists() {
+                return Err("Gemini CLI 配置文件不存在".to_string());
+            }
+            crate::config::read_json_file::<serde_json::Value>(&settings_path)?
+        }
     };
 
     // 创建默认供应商（仅首次初始化）
@@ -605,10 +682,7 @@ pub async fn get_config_status(
     app: Option<String>,
     appType: Option<String>,
 ) -> Result<ConfigStatus, String> {
-    let app = app_type
-        .or_else(|| app.as_deref().map(|s| s.into()))
-        .or_else(|| appType.as_deref().map(|s| s.into()))
-        .unwrap_or(AppType::Claude);
+    let app = resolve_app_type(app_type, app, appType)?;
 
     match app {
         AppType::Claude => Ok(crate::config::get_claude_config_status()),
@@ -622,6 +696,13 @@ pub async fn get_config_status(
 
             Ok(ConfigStatus { exists, path })
         }
+        AppType::Gemini => {
+            let path = crate::config::get_gemini_settings_path();
+            Ok(ConfigStatus {
+                exists: path.exists(),
+                path: path.to_string_lossy().to_string(),
+            })
+        }
     }
 }
 
@@ -638,14 +719,12 @@ pub async fn get_config_dir(
     app: Option<String>,
     appType: Option<String>,
 ) -> Result<String, String> {
-    let app = app_type
-        .or_else(|| app.as_deref().map(|s| s.into()))
-        .or_else(|| appType.as_deref().map(|s| s.into()))
-        .unwrap_or(AppType::Claude);
+    let app = resolve_app_type(app_type, app, appType)?;
 
     let dir = match app {
         AppType::Claude => config::get_claude_config_dir(),
         AppType::Codex => codex_config::get_codex_config_dir(),
+        AppType::Gemini => config::get_gemini_config_dir(),
     };
 
     Ok(dir.to_string_lossy().to_string())
@@ -660,14 +739,12 @@ pub async fn open_config_folder(
     app: Option<String>,
     appType: Option<String>,
 ) -> Result<bool, String> {
-    let app_type = app_type
-        .or_else(|| app.as_deref().map(|s| s.into()))
-        .or_else(|| appType.as_deref().map(|s| s.into()))
-        .unwrap_or(AppType::Claude);
+    let app_type = resolve_app_type(app_type, app, appType)?;
 
     let config_dir = match app_type {
         AppType::Claude => crate::config::get_claude_config_dir(),
         AppType::Codex => crate::codex_config::get_codex_config_dir(),
+        AppType::Gemini => crate::config::get_gemini_config_dir(),
     };
 
     // 确保目录存在
@@ -821,7 +898,7 @@ pub async fn get_mcp_config(
         .config
         .lock()
         .map_err(|e| format!("获取锁失败: {}", e))?;
-    let app_ty = crate::app_config::AppType::from(app.as_deref().unwrap_or("claude"));
+    let app_ty = app.as_deref().unwrap_or("claude").parse::<AppType>()?;
     let (servers, normalized) = crate::mcp::get_servers_snapshot_for(&mut cfg, &app_ty);
     let need_save = normalized > 0;
     drop(cfg);
@@ -847,7 +924,7 @@ pub async fn upsert_mcp_server_in_config(
         .config
         .lock()
         .map_err(|e| format!("获取锁失败: {}", e))?;
-    let app_ty = crate::app_config::AppType::from(app.as_deref().unwrap_or("claude"));
+    let app_ty = app.as_deref().unwrap_or("claude").parse::<AppType>()?;
     let mut sync_targets: Vec<crate::app_config::AppType> = Vec::new();
 
     let changed = crate::mcp::upsert_in_config_for(&mut cfg, &app_ty, &id, spec.clone())?;
@@ -863,11 +940,13 @@ pub async fn upsert_mcp_server_in_config(
         sync_targets.push(app_ty.clone());
     }
 
-    if sync_other_side.unwrap_or(false) {
-        let other_app = match app_ty.clone() {
-            crate::app_config::AppType::Claude => crate::app_config::AppType::Codex,
-            crate::app_config::AppType::Codex => crate::app_config::AppType::Claude,
-        };
+    // Claude 与 Codex 互为另一端；Gemini 没有对应的另一端
+    let other_side = match app_ty {
+        crate::app_config::AppType::Claude => Some(crate::app_config::AppType::Codex),
+        crate::app_config::AppType::Codex => Some(crate::app_config::AppType::Claude),
+        crate::app_config::AppType::Gemini => None,
+    };
+    if let Some(other_app) = other_side.filter(|_| sync_other_side.unwrap_or(false)) {
         crate::mcp::upsert_in_config_for(&mut cfg, &other_app, &id, spec)?;
 
         let should_sync_other = cfg
@@ -892,6 +971,8 @@ pub async fn upsert_mcp_server_in_config(
         match app_ty_to_sync {
             crate::app_config::AppType::Claude => crate::mcp::sync_enabled_to_claude(&cfg2)?,
             crate::app_config::AppType::Codex => crate::mcp::sync_enabled_to_codex(&cfg2)?,
+            // Gemini 暂不支持 MCP 投影
+            crate::app_config::AppType::Gemini => {}
         };
     }
     Ok(changed)
@@ -908,7 +989,7 @@ pub async fn delete_mcp_server_in_config(
         .config
         .lock()
         .map_err(|e| format!("获取锁失败: {}", e))?;
-    let app_ty = crate::app_config::AppType::from(app.as_deref().unwrap_or("claude"));
+    let app_ty = app.as_deref().unwrap_or("claude").parse::<AppType>()?;
     let existed = crate::mcp::delete_in_config_for(&mut cfg, &app_ty, &id)?;
     drop(cfg);
     state.save()?;
@@ -920,6 +1001,8 @@ pub async fn delete_mcp_server_in_config(
     match app_ty {
         crate::app_config::AppType::Claude => crate::mcp::sync_enabled_to_claude(&cfg2)?,
         crate::app_config::AppType::Codex => crate::mcp::sync_enabled_to_codex(&cfg2)?,
+        // Gemini 暂不支持 MCP 投影
+        crate::app_config::AppType::Gemini => {}
     }
     Ok(existed)
 }
@@ -936,7 +1019,7 @@ pub async fn set_mcp_enabled(
         .config
         .lock()
         .map_err(|e| format!("获取锁失败: {}", e))?;
-    let app_ty = crate::app_config::AppType::from(app.as_deref().unwrap_or("claude"));
+    let app_ty = app.as_deref().unwrap_or("claude").parse::<AppType>()?;
     let changed = crate::mcp::set_enabled_and_sync_for(&mut cfg, &app_ty, &id, enabled)?;
     drop(cfg);
     state.save()?;
@@ -1007,19 +1090,74 @@ pub async fn import_mcp_from_codex(state: State<'_, AppState>) -> Result<usize,
     Ok(changed)
 }
 
+/// 将指定客户端的 MCP 配置导出为独立文件（可选清空疑似密钥的 env / headers 值）
+#[tauri::command]
+pub async fn export_mcp_config(
+    state: State<'_, AppState>,
+    app: Option<String>,
+    path: String,
+    strip_secrets: Option<bool>,
+) -> Result<usize, String> {
+    let mut cfg = state
+        .config
+        .lock()
+        .map_err(|e| format!("获取锁失败: {}", e))?;
+    let app_ty = app.as_deref().unwrap_or("claude").parse::<AppType>()?;
+    crate::mcp::export_to_file(
+        &mut cfg,
+        &app_ty,
+        std::path::Path::new(&path),
+        strip_secrets.unwrap_or(true),
+    )
+}
+
+/// 从独立文件导入 MCP 配置（合并或替换），并同步启用项到对应客户端
+#[tauri::command]
+pub async fn import_mcp_config(
+    state: State<'_, AppState>,
+    app: Option<String>,
+    path: String,
+    merge: bool,
+    overwrite: Option<bool>,
+) -> Result<usize, String> {
+    let mut cfg = state
+        .config
+        .lock()
+        .map_err(|e| format!("获取锁失败: {}", e))?;
+    let app_ty = app.as_deref().unwrap_or("claude").parse::<AppType>()?;
+    let changed = crate::mcp::import_from_file(
+        &mut cfg,
+        &app_ty,
+        std::path::Path::new(&path),
+        merge,
+        overwrite.unwrap_or(false),
+    )?;
+    drop(cfg);
+    state.save()?;
+
+    let cfg2 = state
+        .config
+        .lock()
+        .map_err(|e| format!("获取锁失败: {}", e))?;
+    match app_ty {
+        crate::app_config::AppType::Claude => crate::mcp::sync_enabled_to_claude(&cfg2)?,
+        crate::app_config::AppType::Codex => crate::mcp::sync_enabled_to_codex(&cfg2)?,
+        // Gemini 暂不支持 MCP 投影
+        crate::app_config::AppType::Gemini => {}
+    }
+    Ok(changed)
+}
+
 /// 读取当前生效（live）的配置内容，返回可直接作为 provider.settings_config 的对象
 /// - Codex: 返回 { auth: JSON, config: string }
-/// - Claude: 返回 settings.json 的 JSON 内容
+/// - Claude / Gemini: 返回 settings.json 的 JSON 内容
 #[tauri::command]
 pub async fn read_live_provider_settings(
     app_type: Option<AppType>,
     app: Option<String>,
     appType: Option<String>,
 ) -> Result<serde_json::Value, String> {
-    let app_type = app_type
-        .or_else(|| app.as_deref().map(|s| s.into()))
-        .or_else(|| appType.as_deref().map(|s| s.into()))
-        .unwrap_or(AppType::Claude);
+    let app_type = resolve_app_type(app_type, app, appType)?;
 
     match app_type {
         AppType::Codex => {
@@ -1039,9 +1177,129 @@ pub async fn read_live_provider_settings(
             let v: serde_json::Value = crate::config::read_json_file(&path)?;
             Ok(v)
         }
+        AppType::Gemini => {
+            let path = crate::config::get_gemini_settings_path();
+            if !path.exists() {
+                return Err("Gemini CLI 配置文件不存在".to_string());
+            }
+            let v: serde_json::Value = crate::config::read_json_file(&path)?;
+            Ok(v)
+        }
     }
 }
 
+/// 诊断配置文件的编码/BOM 问题及首个 JSON 解析错误位置（默认检查 ~/.cc-switch/config.json）
+#[tauri::command]
+pub async fn diagnose_config_file(
+    path: Option<String>,
+) -> Result<crate::config::ConfigFileDiagnosis, String> {
+    let path = match path {
+        Some(p) if !p.trim().is_empty() => std::path::PathBuf::from(p),
+        _ => crate::config::get_app_config_path(),
+    };
+    crate::config::diagnose_json_file(&path)
+}
+
+// ==================== 配置方案（Profiles） ====================
+
+/// 将当前完整配置保存为命名配置方案
+#[tauri::command]
+pub async fn save_profile(state: State<'_, AppState>, name: String) -> Result<(), String> {
+    let config = state
+        .config
+        .lock()
+        .map_err(|e| format!("获取锁失败: {}", e))?;
+    config.save_profile(&name)
+}
+
+/// 读取命名配置方案的内容（不切换）
+#[tauri::command]
+pub async fn load_profile(name: String) -> Result<crate::app_config::MultiAppConfig, String> {
+    crate::app_config::MultiAppConfig::load_profile(&name)
+}
+
+/// 列出所有已保存的配置方案
+#[tauri::command]
+pub async fn list_profiles() -> Result<Vec<String>, String> {
+    crate::app_config::MultiAppConfig::list_profiles()
+}
+
+/// 切换到命名配置方案：先回填 live 配置并备份当前配置，再整体替换并持久化
+///
+/// 替换前会把方案中各应用的当前供应商写入 live 配置（Claude/Gemini settings.json、
+/// Codex auth.json + config.toml、Factory config.json），与 switch_provider 的写入路径一致。
+/// 否则 live 文件仍是旧方案的供应商，下次 switch_provider 回填时会覆盖新方案的配置。
+/// 任一应用写入失败时，已写入的 live 文件恢复为切换前的内容，且不替换当前配置。
+#[tauri::command]
+pub async fn switch_profile(state: State<'_, AppState>, name: String) -> Result<String, String> {
+    let profile = crate::app_config::MultiAppConfig::load_profile(&name)?;
+
+    let mut config = state
+        .config
+        .lock()
+        .map_err(|e| format!("获取锁失败: {}", e))?;
+
+    // 与 switch_provider 一致：先回填 live 配置到各应用的当前供应商，手动修改不会丢失
+    for app_type in [AppType::Claude, AppType::Codex, AppType::Gemini] {
+        let Some(live) = read_live_settings(&app_type)? else {
+            continue;
+        };
+        if let Some(manager) = config.get_manager_mut(&app_type) {
+            let current = manager.current.clone();
+            if let Some(provider) = manager.providers.get_mut(&current) {
+                provider.settings_config = live;
+            }
+        }
+    }
+    config.save()?;
+
+    // 备份当前配置（已包含回填的 live 配置）
+    let config_path = crate::config::get_app_config_path();
+    let backup_id = crate::import_export::create_backup(&config_path)?;
+
+    // 写入前记录所有 live 文件，任一步失败时整体回滚
+    let snapshot = snapshot_files(vec![
+        get_claude_settings_path(),
+        crate::config::get_gemini_settings_path(),
+        codex_config::get_codex_auth_path(),
+        codex_config::get_codex_config_path(),
+        crate::droid_config::get_factory_config_path()?,
+    ])?;
+    if let Err(e) = apply_profile_live(&profile).and_then(|_| profile.save()) {
+        restore_files(snapshot);
+        return Err(e);
+    }
+    *config = profile;
+
+    Ok(backup_id)
+}
+
+/// 将方案中各应用的当前供应商写入 live 配置
+fn apply_profile_live(profile: &crate::app_config::MultiAppConfig) -> Result<(), String> {
+    for app_type in [AppType::Claude, AppType::Codex, AppType::Gemini] {
+        let Some(manager) = profile.get_manager(&app_type) else {
+            continue;
+        };
+        let Some(provider) = manager.providers.get(&manager.current) else {
+            continue;
+        };
+        write_live_settings(&app_type, provider)?;
+        if let AppType::Codex = app_type {
+            crate::mcp::sync_enabled_to_codex(profile)?;
+        }
+    }
+    if let Some(droid_manager) = &profile.droid_manager {
+        if let Some(provider) = droid_manager
+            .providers
+            .iter()
+            .find(|p| p.id == droid_manager.current)
+        {
+            crate::droid_config::apply_provider_to_factory(provider, false)?;
+        }
+    }
+    Ok(())
+}
+
 /// 获取设置
 #[tauri::command]
 pub async fn get_settings() -> Result<crate::settings::AppSettings, String> {
@@ -1138,10 +1396,7 @@ pub async fn get_custom_endpoints(
     provider_id: Option<String>,
     providerId: Option<String>,
 ) -> Result<Vec<crate::settings::CustomEndpoint>, String> {
-    let app_type = app_type
-        .or_else(|| app.as_deref().map(|s| s.into()))
-        .or_else(|| appType.as_deref().map(|s| s.into()))
-        .unwrap_or(AppType::Claude);
+    let app_type = resolve_app_type(app_type, app, appType)?;
     let provider_id = provider_id
         .or(providerId)
         .ok_or_else(|| "缺少 providerId".to_string())?;
@@ -1180,10 +1435,7 @@ pub async fn add_custom_endpoint(
     providerId: Option<String>,
     url: String,
 ) -> Result<(), String> {
-    let app_type = app_type
-        .or_else(|| app.as_deref().map(|s| s.into()))
-        .or_else(|| appType.as_deref().map(|s| s.into()))
-        .unwrap_or(AppType::Claude);
+    let app_type = resolve_app_type(app_type, app, appType)?;
     let provider_id = provider_id
         .or(providerId)
         .ok_or_else(|| "缺少 providerId".to_string())?;
@@ -1232,10 +1484,7 @@ pub async fn remove_custom_endpoint(
     providerId: Option<String>,
     url: String,
 ) -> Result<(), String> {
-    let app_type = app_type
-        .or_else(|| app.as_deref().map(|s| s.into()))
-        .or_else(|| appType.as_deref().map(|s| s.into()))
-        .unwrap_or(AppType::Claude);
+    let app_type = resolve_app_type(app_type, app, appType)?;
     let provider_id = provider_id
         .or(providerId)
         .ok_or_else(|| "缺少 providerId".to_string())?;
@@ -1270,10 +1519,7 @@ pub async fn update_endpoint_last_used(
     providerId: Option<String>,
     url: String,
 ) -> Result<(), String> {
-    let app_type = app_type
-        .or_else(|| app.as_deref().map(|s| s.into()))
-        .or_else(|| appType.as_deref().map(|s| s.into()))
-        .unwrap_or(AppType::Claude);
+    let app_type = resolve_app_type(app_type, app, appType)?;
     let provider_id = provider_id
         .or(providerId)
         .ok_or_else(|| "缺少 providerId".to_string())?;
@@ -1322,6 +1568,57 @@ pub async fn get_droid_providers(
     }
 }
 
+/// 获取加载配置时被跳过的无效 Droid Provider 的错误信息
+#[tauri::command]
+pub async fn get_droid_provider_errors(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    let config = state
+        .config
+        .lock()
+        .map_err(|e| format!("获取锁失败: {}", e))?;
+    
+    Ok(config
+        .droid_manager
+        .as_ref()
+        .map(|m| m.load_errors.clone())
+        .unwrap_or_default())
+}
+
+/// 按名称、模型或 Base URL 搜索 Droid Providers（不区分大小写）
+#[tauri::command]
+pub async fn search_droid_providers(
+    state: State<'_, AppState>,
+    query: String,
+) -> Result<Vec<crate::droid_config::DroidProvider>, String> {
+    let config = state
+        .config
+        .lock()
+        .map_err(|e| format!("获取锁失败: {}", e))?;
+    
+    Ok(config
+        .droid_manager
+        .as_ref()
+        .map(|m| crate::droid_config::search_providers(&m.providers, &query))
+        .unwrap_or_default())
+}
+
+/// 获取带有指定标签的 Droid Providers
+#[tauri::command]
+pub async fn list_droid_providers_by_tag(
+    state: State<'_, AppState>,
+    tag: String,
+) -> Result<Vec<crate::droid_config::DroidProvider>, String> {
+    let config = state
+        .config
+        .lock()
+        .map_err(|e| format!("获取锁失败: {}", e))?;
+    
+    Ok(config
+        .droid_manager
+        .as_ref()
+        .map(|m| crate::droid_config::list_providers_by_tag(&m.providers, &tag))
+        .unwrap_or_default())
+}
+
 /// 获取当前 Droid Provider ID
 #[tauri::command]
 pub async fn get_current_droid_provider(
@@ -1411,7 +1708,7 @@ pub async fn update_droid_provider(
                 
                 // 应用新配置
                 println!("[DEBUG] Applying provider to Factory config");
-                if let Err(e) = crate::droid_config::apply_provider_to_factory(&provider) {
+                if let Err(e) = crate::droid_config::apply_provider_to_factory(&provider, false) {
                     println!("[ERROR] Failed to apply provider to factory: {}", e);
                     return Err(format!("应用配置到 Factory 失败: {}", e));
                 }
@@ -1466,10 +1763,13 @@ pub async fn delete_droid_provider(
 }
 
 /// 切换 Droid Provider
+///
+/// `skip_backup` 为 true 时跳过 Factory 配置的 .bak 备份，适合频繁切换测试
 #[tauri::command]
 pub async fn switch_droid_provider(
     state: State<'_, AppState>,
     id: String,
+    skip_backup: Option<bool>,
 ) -> Result<(), String> {
     let mut config = state
         .config
@@ -1486,7 +1786,7 @@ pub async fn switch_droid_provider(
         drop(config);
         
         // 应用到 Factory 配置
-        crate::droid_config::apply_provider_to_factory(&provider)?;
+        crate::droid_config::apply_provider_to_factory(&provider, skip_backup.unwrap_or(false))?;
         state.save()?;
         Ok(())
     } else {
@@ -1494,48 +1794,114 @@ pub async fn switch_droid_provider(
     }
 }
 
-/// 查询 Droid Provider 余额
+/// 一键切换结果（按应用）
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SwitchAllEntry {
+    pub app: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider_id: Option<String>,
+    pub switched: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// 按 id 优先、名称（忽略大小写）其次查找匹配的供应商
+fn resolve_provider_ref<'a>(
+    candidates: impl Iterator<Item = (&'a str, &'a str)> + Clone,
+    provider_ref: &str,
+) -> Option<String> {
+    let needle = provider_ref.trim();
+    candidates
+        .clone()
+        .find(|(id, _)| *id == needle)
+        .or_else(|| {
+            candidates
+                .into_iter()
+                .find(|(_, name)| name.trim().eq_ignore_ascii_case(needle))
+        })
+        .map(|(id, _)| id.to_string())
+}
+
+/// 将 Claude、Codex、Droid 同时切换到同一个供应商（按 id 或名称匹配），返回各应用的切换结果
 #[tauri::command]
-pub async fn fetch_droid_balance(api_key: String) -> Result<serde_json::Value, String> {
-    use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, USER_AGENT, ACCEPT};
+pub async fn switch_all_to_provider(
+    state: State<'_, AppState>,
+    provider_ref: String,
+) -> Result<Vec<SwitchAllEntry>, String> {
+    let (claude_id, codex_id, droid_id) = {
+        let config = state
+            .config
+            .lock()
+            .map_err(|e| format!("获取锁失败: {}", e))?;
+        let find_in = |app_type: &AppType| {
+            config.get_manager(app_type).and_then(|manager| {
+                resolve_provider_ref(
+                    manager
+                        .providers
+                        .values()
+                        .map(|p| (p.id.as_str(), p.name.as_str())),
+                    &provider_ref,
+                )
+            })
+        };
+        let droid_id = config.droid_manager.as_ref().and_then(|manager| {
+            resolve_provider_ref(
+                manager
+                    .providers
+                    .iter()
+                    .map(|p| (p.id.as_str(), p.name.as_str())),
+                &provider_ref,
+            )
+        });
+        (find_in(&AppType::Claude), find_in(&AppType::Codex), droid_id)
+    };
     
-    // 构建请求头
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        AUTHORIZATION,
-        HeaderValue::from_str(&format!("Bearer {}", api_key))
-            .map_err(|e| format!("Invalid API key format: {}", e))?
-    );
-    headers.insert(
-        USER_AGENT,
-        HeaderValue::from_static("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/141.0.0.0 Safari/537.36")
-    );
-    headers.insert(
-        ACCEPT,
-        HeaderValue::from_static("*/*")
-    );
+    let mut report = Vec::new();
+    for (app_type, matched) in [(AppType::Claude, claude_id), (AppType::Codex, codex_id)] {
+        let app = app_type.as_str().to_string();
+        let Some(id) = matched else {
+            report.push(SwitchAllEntry {
+                app,
+                provider_id: None,
+                switched: false,
+                message: Some("未找到匹配的供应商".to_string()),
+            });
+            continue;
+        };
+        let result = switch_provider(state.clone(), Some(app_type), None, None, id.clone()).await;
+        report.push(SwitchAllEntry {
+            app,
+            provider_id: Some(id),
+            switched: result.is_ok(),
+            message: result.err(),
+        });
+    }
     
-    // 创建HTTP客户端
-    let client = reqwest::Client::builder()
-        .default_headers(headers)
-        .timeout(std::time::Duration::from_secs(30))
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    match droid_id {
+        Some(id) => {
+            let result = switch_droid_provider(state.clone(), id.clone(), None).await;
+            report.push(SwitchAllEntry {
+                app: "droid".to_string(),
+                provider_id: Some(id),
+                switched: result.is_ok(),
+                message: result.err(),
+            });
+        }
+        None => report.push(SwitchAllEntry {
+            app: "droid".to_string(),
+            provider_id: None,
+            switched: false,
+            message: Some("未找到匹配的供应商".to_string()),
+        }),
+    }
     
-    // 发送请求
-    let response = client
-        .get("https://app.factory.ai/api/organization/members/chat-usage")
-        .send()
-        .await
-        .map_err(|e| {
-            if e.is_timeout() {
-                "Request timeout after 30 seconds".to_string()
-            } else if e.is_connect() {
-                format!("Connection failed: {}", e)
-            } else {
-                format!("Request failed: {}", e)
-            }
-        })?;
+    Ok(report)
+}
+
+/// 查询 Droid Provider 余额
+#[tauri::command]
+pub async fn fetch_droid_balance(api_key: String) -> Result<serde_json::Value, String> {
+    let response = crate::droid_config::send_usage_request(&api_key).await?;
     
     // 检查响应状态
     let status = response.status();
@@ -1576,6 +1942,219 @@ pub async fn fetch_multiple_droid_balances(api_keys: Vec<String>) -> Result<Vec<
     Ok(balances)
 }
 
+/// 刷新 Provider 下余额已过期的 API Key 并保存（401 的 Key 会被停用），返回实际更新的数量
+///
+/// max_age_secs 默认 300 秒，在此时间内查询过的 Key 会被跳过。
+#[tauri::command]
+pub async fn refresh_droid_balances(
+    state: State<'_, AppState>,
+    provider_id: String,
+    max_age_secs: Option<u64>,
+) -> Result<usize, String> {
+    // 请求期间不持有配置锁
+    let mut provider = {
+        let config = state
+            .config
+            .lock()
+            .map_err(|e| format!("获取锁失败: {}", e))?;
+        config
+            .droid_manager
+            .as_ref()
+            .and_then(|m| m.providers.iter().find(|p| p.id == provider_id))
+            .cloned()
+            .ok_or_else(|| format!("Provider {} 不存在", provider_id))?
+    };
+    
+    let max_age_secs =
+        max_age_secs.unwrap_or(crate::droid_config::DEFAULT_BALANCE_MAX_AGE_SECS);
+    let refreshed = crate::droid_config::refresh_balances(&mut provider, max_age_secs).await?;
+    
+    let mut config = state
+        .config
+        .lock()
+        .map_err(|e| format!("获取锁失败: {}", e))?;
+    let stored_keys = config
+        .droid_manager
+        .as_mut()
+        .and_then(|m| m.providers.iter_mut().find(|p| p.id == provider_id))
+        .and_then(|p| p.api_keys.as_mut())
+        .ok_or_else(|| format!("Provider {} 不存在", provider_id))?;
+    // 按 Key id 合并，避免覆盖刷新期间的其他修改
+    for refreshed in provider.api_keys.unwrap_or_default() {
+        if let Some(key) = stored_keys.iter_mut().find(|k| k.id == refreshed.id) {
+            key.balance = refreshed.balance;
+            key.is_active = key.is_active && refreshed.is_active;
+        }
+    }
+    
+    drop(config);
+    state.save()?;
+    Ok(refreshed)
+}
+
+/// 检查 Provider 下所有 API Key 是否仍然有效，返回 (key_id, 是否有效)
+#[tauri::command]
+pub async fn check_all_droid_keys(
+    state: State<'_, AppState>,
+    provider_id: String,
+) -> Result<Vec<(String, bool)>, String> {
+    // 请求期间不持有配置锁
+    let mut provider = {
+        let config = state
+            .config
+            .lock()
+            .map_err(|e| format!("获取锁失败: {}", e))?;
+        config
+            .droid_manager
+            .as_ref()
+            .and_then(|m| m.providers.iter().find(|p| p.id == provider_id))
+            .cloned()
+            .ok_or_else(|| format!("Provider {} 不存在", provider_id))?
+    };
+    
+    let report = crate::droid_config::check_all_keys(&mut provider).await?;
+    
+    let mut config = state
+        .config
+        .lock()
+        .map_err(|e| format!("获取锁失败: {}", e))?;
+    let droid_manager = config
+        .droid_manager
+        .as_mut()
+        .ok_or_else(|| "Droid manager 未初始化".to_string())?;
+    let is_current = droid_manager.current == provider_id;
+    let stored = droid_manager
+        .providers
+        .iter_mut()
+        .find(|p| p.id == provider_id)
+        .ok_or_else(|| format!("Provider {} 不存在", provider_id))?;
+    if let Some(stored_keys) = stored.api_keys.as_mut() {
+        // 按 Key id 合并，避免覆盖检查期间的其他修改；只停用失效的 Key，不重新启用
+        for checked in provider.api_keys.unwrap_or_default() {
+            if let Some(key) = stored_keys.iter_mut().find(|k| k.id == checked.id) {
+                key.is_active = key.is_active && checked.is_active;
+                key.balance = checked.balance;
+            }
+        }
+    }
+    stored.is_invalid = provider.is_invalid;
+    let reselected = crate::droid_config::reselect_if_current_disabled(stored);
+    let changed_provider = if is_current && reselected {
+        Some(stored.clone())
+    } else {
+        None
+    };
+    
+    drop(config);
+    
+    // 当前 Key 失效后已切换到下一个，同步到 Factory 配置
+    if let Some(provider) = changed_provider {
+        crate::droid_config::apply_provider_to_factory(&provider, false)?;
+    }
+    state.save()?;
+    Ok(report)
+}
+
+/// 复制 Droid Provider（新 id，清空余额缓存），返回新 Provider
+#[tauri::command]
+pub async fn duplicate_droid_provider(
+    state: State<'_, AppState>,
+    provider_id: String,
+    new_name: String,
+) -> Result<crate::droid_config::DroidProvider, String> {
+    let mut config = state
+        .config
+        .lock()
+        .map_err(|e| format!("获取锁失败: {}", e))?;
+    
+    let copy = config
+        .droid_manager
+        .as_mut()
+        .ok_or_else(|| "Droid manager 未初始化".to_string())?
+        .duplicate_provider(&provider_id, &new_name)?;
+    
+    drop(config);
+    state.save()?;
+    Ok(copy)
+}
+
+/// 切换 Droid Provider，连通性测试失败时恢复原 Factory 配置且不切换
+#[tauri::command]
+pub async fn switch_droid_provider_safe(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<(), String> {
+    let provider = {
+        let config = state
+            .config
+            .lock()
+            .map_err(|e| format!("获取锁失败: {}", e))?;
+        config
+            .droid_manager
+            .as_ref()
+            .ok_or_else(|| "Droid manager 未初始化".to_string())?
+            .providers
+            .iter()
+            .find(|p| p.id == id)
+            .cloned()
+            .ok_or_else(|| format!("Provider {} 不存在", id))?
+    };
+    
+    crate::droid_config::switch_provider_safe(&provider).await?;
+    
+    let mut config = state
+        .config
+        .lock()
+        .map_err(|e| format!("获取锁失败: {}", e))?;
+    if let Some(droid_manager) = &mut config.droid_manager {
+        droid_manager.current = id;
+    }
+    
+    drop(config);
+    state.save()?;
+    Ok(())
+}
+
+/// 测试 Droid Provider 的连通性（鉴权、延迟）
+#[tauri::command]
+pub async fn test_droid_provider(
+    state: State<'_, AppState>,
+    provider_id: String,
+) -> Result<crate::droid_config::ProviderTestResult, String> {
+    let provider = {
+        let config = state
+            .config
+            .lock()
+            .map_err(|e| format!("获取锁失败: {}", e))?;
+        config
+            .droid_manager
+            .as_ref()
+            .and_then(|m| m.providers.iter().find(|p| p.id == provider_id))
+            .cloned()
+            .ok_or_else(|| format!("Provider {} 不存在", provider_id))?
+    };
+    
+    crate::droid_config::test_provider(&provider).await
+}
+
+/// 查找余额缓存已过期（或从未查询）的 API Key，返回 (provider_id, key_id)
+#[tauri::command]
+pub async fn find_stale_balances(
+    state: State<'_, AppState>,
+    max_age_secs: u64,
+) -> Result<Vec<(String, String)>, String> {
+    let config = state
+        .config
+        .lock()
+        .map_err(|e| format!("获取锁失败: {}", e))?;
+    
+    Ok(config
+        .droid_manager
+        .as_ref()
+        .map(|m| crate::droid_config::find_stale_balances(&m.providers, max_age_secs))
+        .unwrap_or_default())
+}
+
 /// 根据策略自动切换到下一个 API Key
 #[tauri::command]
 pub async fn auto_switch_droid_key(
@@ -1589,50 +2168,11 @@ pub async fn auto_switch_droid_key(
     
     if let Some(droid_manager) = &mut config.droid_manager {
         if let Some(provider) = droid_manager.providers.iter_mut().find(|p| p.id == provider_id) {
-            if let Some(api_keys) = &provider.api_keys {
-                if api_keys.is_empty() {
-                    return Err("没有可用的API Key".to_string());
-                }
-                
-                let current_index = provider.current_key_index.unwrap_or(0);
-                let next_index = match provider.switch_strategy.as_ref().unwrap_or(&crate::droid_config::SwitchStrategy::Manual) {
-                    crate::droid_config::SwitchStrategy::RoundRobin => {
-                        // 轮询策略
-                        (current_index + 1) % api_keys.len()
-                    },
-                    crate::droid_config::SwitchStrategy::UseLowest => {
-                        // 使用余额最低的
-                        let mut min_index = 0;
-                        let mut min_remaining = f64::MAX;
-                        for (i, key) in api_keys.iter().enumerate() {
-                            if let Some(balance) = &key.balance {
-                                if balance.remaining < min_remaining {
-                                    min_remaining = balance.remaining;
-                                    min_index = i;
-                                }
-                            }
-                        }
-                        min_index
-                    },
-                    crate::droid_config::SwitchStrategy::UseHighest => {
-                        // 使用余额最高的
-                        let mut max_index = 0;
-                        let mut max_remaining = 0.0;
-                        for (i, key) in api_keys.iter().enumerate() {
-                            if let Some(balance) = &key.balance {
-                                if balance.remaining > max_remaining {
-                                    max_remaining = balance.remaining;
-                                    max_index = i;
-                                }
-                            }
-                        }
-                        max_index
-                    },
-                    _ => current_index, // Manual模式不自动切换
-                };
+            if provider.api_keys.is_some() {
+                let next_index = crate::droid_config::select_next_key_index(provider)
+                    .ok_or_else(|| "没有可用的API Key".to_string())?;
                 
-                provider.current_key_index = Some(next_index);
-                provider.api_key = api_keys[next_index].key.clone();
+                crate::droid_config::select_key(provider, next_index);
                 
                 drop(config);
                 state.save()?;
@@ -1644,6 +2184,147 @@ pub async fn auto_switch_droid_key(
     Err("Provider not found".to_string())
 }
 
+/// 模拟切换策略接下来 count 次会选用的 Key 索引（不修改任何状态）
+#[tauri::command]
+pub async fn simulate_rotation(
+    state: State<'_, AppState>,
+    provider_id: String,
+    count: usize,
+) -> Result<Vec<usize>, String> {
+    let config = state
+        .config
+        .lock()
+        .map_err(|e| format!("获取锁失败: {}", e))?;
+    
+    let provider = config
+        .droid_manager
+        .as_ref()
+        .and_then(|m| m.providers.iter().find(|p| p.id == provider_id))
+        .ok_or_else(|| format!("Provider {} 不存在", provider_id))?;
+    
+    crate::droid_config::simulate_rotation(provider, count)
+}
+
+/// 修改 API Key 的名称或启用状态
+#[tauri::command]
+pub async fn update_api_key(
+    state: State<'_, AppState>,
+    provider_id: String,
+    key_id: String,
+    name: Option<String>,
+    is_active: Option<bool>,
+) -> Result<(), String> {
+    let mut config = state
+        .config
+        .lock()
+        .map_err(|e| format!("获取锁失败: {}", e))?;
+    
+    let droid_manager = config
+        .droid_manager
+        .as_mut()
+        .ok_or_else(|| "Droid manager 未初始化".to_string())?;
+    let is_current = droid_manager.current == provider_id;
+    let provider = droid_manager
+        .providers
+        .iter_mut()
+        .find(|p| p.id == provider_id)
+        .ok_or_else(|| format!("Provider {} 不存在", provider_id))?;
+    
+    let old_api_key = provider.api_key.clone();
+    crate::droid_config::update_api_key(provider, &key_id, name, is_active)?;
+    let changed_provider = if is_current && provider.api_key != old_api_key {
+        Some(provider.clone())
+    } else {
+        None
+    };
+    
+    drop(config);
+    
+    // 当前 Provider 的 Key 发生变化时同步到 Factory 配置
+    if let Some(provider) = changed_provider {
+        crate::droid_config::apply_provider_to_factory(&provider, false)?;
+    }
+    state.save()?;
+    Ok(())
+}
+
+/// 上游返回 401 时停用对应 Key 并切换到下一个可用 Key，返回新 Key 的 id（已无可用 Key 时返回 None）
+#[tauri::command]
+pub async fn handle_auth_failure(
+    state: State<'_, AppState>,
+    provider_id: String,
+    key_id: String,
+) -> Result<Option<String>, String> {
+    let mut config = state
+        .config
+        .lock()
+        .map_err(|e| format!("获取锁失败: {}", e))?;
+    
+    let droid_manager = config
+        .droid_manager
+        .as_mut()
+        .ok_or_else(|| "Droid manager 未初始化".to_string())?;
+    let is_current = droid_manager.current == provider_id;
+    let provider = droid_manager
+        .providers
+        .iter_mut()
+        .find(|p| p.id == provider_id)
+        .ok_or_else(|| format!("Provider {} 不存在", provider_id))?;
+    
+    let next_key_id = crate::droid_config::handle_auth_failure(provider, &key_id)?;
+    let promoted_provider = if is_current && next_key_id.is_some() {
+        Some(provider.clone())
+    } else {
+        None
+    };
+    
+    drop(config);
+    
+    if let Some(provider) = promoted_provider {
+        crate::droid_config::apply_provider_to_factory(&provider, false)?;
+    }
+    state.save()?;
+    Ok(next_key_id)
+}
+
+/// 校验当前 Droid Provider 正在使用的 API Key 是否仍然有效
+///
+/// droid2api 服务会透传客户端的 Authorization，实际生效的是 cc-switch 写入 Factory
+/// 配置的当前 Key；这里用它发起一次最小的鉴权请求，401/403 视为失效。
+#[tauri::command]
+pub async fn validate_running_proxy_key(state: State<'_, AppState>) -> Result<bool, String> {
+    let api_key = {
+        let config = state
+            .config
+            .lock()
+            .map_err(|e| format!("获取锁失败: {}", e))?;
+        let droid_manager = config
+            .droid_manager
+            .as_ref()
+            .ok_or_else(|| "Droid manager 未初始化".to_string())?;
+        let provider = droid_manager
+            .providers
+            .iter()
+            .find(|p| p.id == droid_manager.current)
+            .ok_or_else(|| "当前没有选中的 Droid Provider".to_string())?;
+        provider.api_key.clone()
+    };
+    
+    if api_key.trim().is_empty() {
+        return Ok(false);
+    }
+    
+    let response = crate::droid_config::send_usage_request(&api_key).await?;
+    let status = response.status();
+    if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+        return Ok(false);
+    }
+    if !status.is_success() {
+        return Err(format!("API returned error {}", status));
+    }
+    Ok(true)
+}
+
 /// 获取 Factory 配置中的自定义模型
 #[tauri::command]
 pub async fn get_factory_custom_models() -> Result<Vec<crate::droid_config::DroidCustomModel>, String> {
@@ -1651,12 +2332,64 @@ pub async fn get_factory_custom_models() -> Result<Vec<crate::droid_config::Droi
     Ok(config.custom_models)
 }
 
+/// 获取 Factory 配置中的自定义模型，并标记是否由 cc-switch 管理
+#[tauri::command]
+pub async fn list_factory_models() -> Result<Vec<crate::droid_config::FactoryModelInfo>, String> {
+    crate::droid_config::list_factory_models()
+}
+
+/// 设置 Factory 默认模型（必须是已存在的自定义模型）
+#[tauri::command]
+pub async fn set_factory_default_model(model_display_name: String) -> Result<(), String> {
+    crate::droid_config::set_factory_default_model(&model_display_name)
+}
+
+/// 校验 Factory 配置文件，返回发现的问题列表
+#[tauri::command]
+pub async fn validate_factory_config() -> Result<Vec<String>, String> {
+    crate::droid_config::validate_factory_config()
+}
+
+/// 删除 Droid 会话记录
+#[tauri::command]
+pub async fn delete_droid_session(session_id: String) -> Result<(), String> {
+    crate::droid_config::delete_droid_session(&session_id)
+}
+
+/// 获取 Factory 会话目录路径（~/.factory/sessions）
+#[tauri::command]
+pub async fn get_factory_sessions_path() -> Result<String, String> {
+    Ok(crate::droid_config::get_factory_sessions_dir()?
+        .to_string_lossy()
+        .to_string())
+}
+
+/// 获取 Factory 配置文件路径（~/.factory/config.json）
+#[tauri::command]
+pub async fn get_factory_config_path_string() -> Result<String, String> {
+    Ok(crate::droid_config::get_factory_config_path()?
+        .to_string_lossy()
+        .to_string())
+}
+
+/// 列出 Factory 配置备份（最新在前）
+#[tauri::command]
+pub async fn list_factory_backups() -> Result<Vec<crate::droid_config::FactoryBackupInfo>, String> {
+    crate::droid_config::list_factory_backups()
+}
+
+/// 从指定时间戳的备份恢复 Factory 配置
+#[tauri::command]
+pub async fn restore_factory_backup(timestamp: i64) -> Result<(), String> {
+    crate::droid_config::restore_factory_backup(timestamp)
+}
+
 /// 删除 Factory 配置中的自定义模型
 #[tauri::command]
 pub async fn delete_factory_custom_model(model_display_name: String) -> Result<(), String> {
     let mut config = crate::droid_config::read_factory_config()?;
     config.custom_models.retain(|m| m.model_display_name != model_display_name);
-    crate::droid_config::write_factory_config(&config)?;
+    crate::droid_config::write_factory_config(&config, false)?;
     Ok(())
 }
 
@@ -1670,11 +2403,14 @@ pub async fn update_factory_custom_model(
     
     // 找到并更新模型
     if let Some(existing) = config.custom_models.iter_mut().find(|m| m.model_display_name == old_display_name) {
+        // 保留 cc-switch 管理标记，避免前端传回的对象缺少该字段
+        let managed = existing.cc_switch_managed;
         *existing = model;
+        existing.cc_switch_managed = existing.cc_switch_managed.or(managed);
     } else {
         return Err(format!("Model {} not found", old_display_name));
     }
     
-    crate::droid_config::write_factory_config(&config)?;
+    crate::droid_config::write_factory_config(&config, false)?;
     Ok(())
 }
@@ -15,6 +15,18 @@ pub fn get_claude_config_dir() -> PathBuf {
         .join(".claude")
 }
 
+/// 获取 Gemini CLI 配置目录路径
+pub fn get_gemini_config_dir() -> PathBuf {
+    dirs::home_dir()
+        .expect("无法获取用户主目录")
+        .join(".gemini")
+}
+
+/// 获取 Gemini CLI 主配置文件路径
+pub fn get_gemini_settings_path() -> PathBuf {
+    get_gemini_config_dir().join("settings.json")
+}
+
 /// 获取 Claude Code 主配置文件路径
 pub fn get_claude_settings_path() -> PathBuf {
     let dir = get_claude_config_dir();
@@ -43,6 +55,11 @@ pub fn get_app_config_path() -> PathBuf {
     get_app_config_dir().join("config.json")
 }
 
+/// 配置方案目录 ~/.cc-switch/profiles
+pub fn get_profiles_dir() -> PathBuf {
+    get_app_config_dir().join("profiles")
+}
+
 /// 归档根目录 ~/.cc-switch/archive
 pub fn get_archive_root() -> PathBuf {
     get_app_config_dir().join("archive")
@@ -112,6 +129,11 @@ pub fn get_provider_config_path(provider_id: &str, provider_name: Option<&str>)
     get_claude_config_dir().join(format!("settings-{}.json", base_name))
 }
 
+/// 去除文本开头的 UTF-8 BOM（Windows 记事本等编辑器保存时可能带上）
+pub fn strip_bom(content: &str) -> &str {
+    content.strip_prefix('\u{feff}').unwrap_or(content)
+}
+
 /// 读取 JSON 配置文件
 pub fn read_json_file<T: for<'a> Deserialize<'a>>(path: &Path) -> Result<T, String> {
     if !path.exists() {
@@ -121,7 +143,8 @@ pub fn read_json_file<T: for<'a> Deserialize<'a>>(path: &Path) -> Result<T, Stri
     let content =
         fs::read_to_string(path).map_err(|e| format!("读取文件失败: {}: {}", path.display(), e))?;
 
-    serde_json::from_str(&content).map_err(|e| format!("解析 JSON 失败: {}: {}", path.display(), e))
+    serde_json::from_str(strip_bom(&content))
+        .map_err(|e| format!("解析 JSON 失败: {}: {}", path.display(), e))
 }
 
 /// 写入 JSON 配置文件
@@ -229,6 +252,73 @@ pub fn delete_file(path: &Path) -> Result<(), String> {
     Ok(())
 }
 
+/// 配置文件诊断结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigFileDiagnosis {
+    pub path: String,
+    pub exists: bool,
+    /// utf-8 / utf-8-bom / utf-16le / utf-16be / invalid-utf8
+    pub encoding: String,
+    pub has_bom: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parse_error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_column: Option<usize>,
+}
+
+/// 诊断 JSON 配置文件的编码、BOM 以及首个解析错误位置
+pub fn diagnose_json_file(path: &Path) -> Result<ConfigFileDiagnosis, String> {
+    let mut diagnosis = ConfigFileDiagnosis {
+        path: path.to_string_lossy().to_string(),
+        exists: path.exists(),
+        encoding: "utf-8".to_string(),
+        has_bom: false,
+        parse_error: None,
+        error_line: None,
+        error_column: None,
+    };
+    if !diagnosis.exists {
+        return Ok(diagnosis);
+    }
+
+    let bytes = fs::read(path).map_err(|e| format!("读取文件失败: {}: {}", path.display(), e))?;
+
+    let body = if let Some(rest) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        diagnosis.encoding = "utf-8-bom".to_string();
+        diagnosis.has_bom = true;
+        rest
+    } else if bytes.starts_with(&[0xFF, 0xFE]) || bytes.starts_with(&[0xFE, 0xFF]) {
+        diagnosis.encoding = if bytes[0] == 0xFF { "utf-16le" } else { "utf-16be" }.to_string();
+        diagnosis.has_bom = true;
+        diagnosis.parse_error = Some("文件为 UTF-16 编码，请另存为 UTF-8".to_string());
+        return Ok(diagnosis);
+    } else {
+        &bytes[..]
+    };
+
+    let text = match std::str::from_utf8(body) {
+        Ok(text) => text,
+        Err(e) => {
+            diagnosis.encoding = "invalid-utf8".to_string();
+            diagnosis.parse_error = Some(format!(
+                "文件不是有效的 UTF-8，首个无效字节位于偏移 {}",
+                e.valid_up_to()
+            ));
+            return Ok(diagnosis);
+        }
+    };
+
+    if let Err(e) = serde_json::from_str::<serde_json::Value>(text) {
+        diagnosis.parse_error = Some(e.to_string());
+        diagnosis.error_line = Some(e.line());
+        diagnosis.error_column = Some(e.column());
+    }
+
+    Ok(diagnosis)
+}
+
 /// 检查 Claude Code 配置状态
 #[derive(Serialize, Deserialize)]
 pub struct ConfigStatus {
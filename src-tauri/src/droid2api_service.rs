@@ -1,77 +1,385 @@
-use std::path::PathBuf;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
 use std::process::{Child, Command, Stdio};
 use std::sync::{Arc, Mutex};
-use tauri::{Manager, State};
+use tauri::{Emitter, Manager, State};
 use serde::{Deserialize, Serialize};
 
+/// 默认实例 id（未指定实例时使用）
+pub const DEFAULT_INSTANCE_ID: &str = "default";
+
+fn default_instance_id() -> String {
+    DEFAULT_INSTANCE_ID.to_string()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServiceStatus {
+    #[serde(default = "default_instance_id")]
+    pub id: String,
     pub running: bool,
     pub port: u16,
     pub pid: Option<u32>,
 }
 
-pub struct Droid2ApiService {
-    pub process: Arc<Mutex<Option<Child>>>,
-    pub status: Arc<Mutex<ServiceStatus>>,
+/// 每个实例保留的日志行数
+const LOG_BUFFER_LINES: usize = 500;
+/// stderr 日志行的前缀
+const STDERR_PREFIX: &str = "[stderr] ";
+
+/// Node 进程输出的环形缓冲区（由读取线程写入）
+pub type LogBuffer = Arc<Mutex<VecDeque<String>>>;
+
+fn push_log(logs: &LogBuffer, line: String) {
+    if let Ok(mut logs) = logs.lock() {
+        if logs.len() >= LOG_BUFFER_LINES {
+            logs.pop_front();
+        }
+        logs.push_back(line);
+    }
 }
 
-impl Droid2ApiService {
-    pub fn new() -> Self {
+/// 最近的 `count` 行 stderr 输出
+fn stderr_tail(logs: &LogBuffer, count: usize) -> Vec<String> {
+    let Ok(logs) = logs.lock() else {
+        return Vec::new();
+    };
+    let mut tail: Vec<String> = logs
+        .iter()
+        .rev()
+        .filter_map(|line| line.strip_prefix(STDERR_PREFIX))
+        .take(count)
+        .map(str::to_string)
+        .collect();
+    tail.reverse();
+    tail
+}
+
+/// 在错误信息后附上最近的 stderr 输出（没有输出时原样返回）
+fn with_stderr_tail(message: String, logs: &LogBuffer) -> String {
+    let tail = stderr_tail(logs, 5);
+    if tail.is_empty() {
+        message
+    } else {
+        format!("{}:\n{}", message, tail.join("\n"))
+    }
+}
+
+/// 单个 droid2api 实例（Node 进程及其状态）
+pub struct Droid2ApiInstance {
+    pub process: Option<Child>,
+    pub status: ServiceStatus,
+    pub logs: LogBuffer,
+    /// 进程意外退出后置位，由 supervisor 处理
+    pub crashed: bool,
+    /// 意外退出时的退出码（被信号终止时为 None）
+    pub exit_code: Option<i32>,
+    /// 自上次手动启动以来的自动重启次数
+    pub restart_count: u32,
+}
+
+impl Droid2ApiInstance {
+    fn new(id: &str) -> Self {
         Self {
-            process: Arc::new(Mutex::new(None)),
-            status: Arc::new(Mutex::new(ServiceStatus {
+            process: None,
+            status: ServiceStatus {
+                id: id.to_string(),
                 running: false,
                 port: 3000,
                 pid: None,
-            })),
+            },
+            logs: Arc::new(Mutex::new(VecDeque::new())),
+            crashed: false,
+            exit_code: None,
+            restart_count: 0,
+        }
+    }
+
+    /// 检查进程是否还在运行，并同步到状态
+    fn refresh(&mut self) {
+        if let Some(child) = self.process.as_mut() {
+            match child.try_wait() {
+                Ok(Some(exit_status)) => {
+                    // 进程已退出（手动停止时 process 已被取走，不会走到这里）
+                    self.status.running = false;
+                    self.status.pid = None;
+                    self.process = None;
+                    self.crashed = true;
+                    self.exit_code = exit_status.code();
+                    log::info!("droid2api instance '{}' process has exited", self.status.id);
+                }
+                Ok(None) => {
+                    // 进程仍在运行
+                    self.status.running = true;
+                }
+                Err(e) => {
+                    log::warn!("Failed to check process status: {}", e);
+                    self.status.running = false;
+                    self.status.pid = None;
+                    self.process = None;
+                }
+            }
+        } else {
+            self.status.running = false;
+            self.status.pid = None;
+        }
+    }
+}
+
+/// droid2api 服务管理：按实例 id 管理多个 Node 进程
+pub struct Droid2ApiService {
+    pub instances: Arc<Mutex<HashMap<String, Droid2ApiInstance>>>,
+}
+
+impl Droid2ApiService {
+    pub fn new() -> Self {
+        let mut instances = HashMap::new();
+        instances.insert(
+            DEFAULT_INSTANCE_ID.to_string(),
+            Droid2ApiInstance::new(DEFAULT_INSTANCE_ID),
+        );
+        Self {
+            instances: Arc::new(Mutex::new(instances)),
+        }
+    }
+
+    /// 获取指定实例的当前状态
+    fn instance_status(&self, id: &str) -> Result<ServiceStatus, String> {
+        let instances = self.instances.lock().map_err(|e| e.to_string())?;
+        instances
+            .get(id)
+            .map(|instance| instance.status.clone())
+            .ok_or_else(|| format!("droid2api instance '{}' not found", id))
+    }
+}
+
+/// 持久化的运行中实例（用于应用重启后恢复）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PersistedInstance {
+    id: String,
+    port: u16,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct PersistedState {
+    #[serde(default)]
+    instances: Vec<PersistedInstance>,
+}
+
+fn persisted_state_path() -> PathBuf {
+    crate::config::get_app_config_dir().join("droid2api_state.json")
+}
+
+fn load_persisted_state() -> PersistedState {
+    let path = persisted_state_path();
+    if !path.exists() {
+        return PersistedState::default();
+    }
+    crate::config::read_json_file(&path).unwrap_or_else(|e| {
+        log::warn!("读取 droid2api 运行状态失败: {}", e);
+        PersistedState::default()
+    })
+}
+
+/// 记录实例的运行状态（running=false 时移除记录）
+fn remember_instance(id: &str, port: u16, running: bool) {
+    let mut state = load_persisted_state();
+    state.instances.retain(|instance| instance.id != id);
+    if running {
+        state.instances.push(PersistedInstance {
+            id: id.to_string(),
+            port,
+        });
+    }
+    if let Err(e) = crate::config::write_json_file(&persisted_state_path(), &state) {
+        log::warn!("保存 droid2api 运行状态失败: {}", e);
+    }
+}
+
+/// 应用启动时恢复上次运行中的 droid2api 实例（需在设置中开启）
+pub fn restore_instances_on_startup(app_handle: &tauri::AppHandle) {
+    if !crate::settings::get_settings().restore_droid2api_on_startup {
+        return;
+    }
+
+    for instance in load_persisted_state().instances {
+        let app_handle = app_handle.clone();
+        tauri::async_runtime::spawn(async move {
+            log::info!("Restoring droid2api instance '{}'", instance.id);
+            let service = app_handle.state::<Droid2ApiService>();
+            if let Err(e) =
+                start_droid2api_service(
+                    service,
+                    app_handle.clone(),
+                    Some(instance.id.clone()),
+                    Some(instance.port),
+                )
+                .await
+            {
+                log::error!("恢复 droid2api 实例 '{}' 失败: {}", instance.id, e);
+            }
+        });
+    }
+}
+
+/// 规范化实例 id，缺省时使用默认实例
+fn instance_key(instance_id: Option<String>) -> String {
+    instance_id
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+        .unwrap_or_else(default_instance_id)
+}
+
+#[cfg(target_os = "windows")]
+const NODE_BINARY: &str = "node.exe";
+#[cfg(not(target_os = "windows"))]
+const NODE_BINARY: &str = "node";
+
+/// 解析 `v20.11.1` 形式的版本目录名
+fn parse_node_version(name: &str) -> Option<(u64, u64, u64)> {
+    let mut parts = name.trim_start_matches('v').split('.').map(|p| p.parse::<u64>());
+    Some((parts.next()?.ok()?, parts.next()?.ok()?, parts.next()?.ok()?))
+}
+
+/// 在版本目录（如 `~/.nvm/versions/node`）下挑选最高版本的 node
+fn highest_version_node(versions_dir: &Path, bin_path: &[&str]) -> Option<PathBuf> {
+    fs::read_dir(versions_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let version = parse_node_version(&entry.file_name().to_string_lossy())?;
+            let mut path = entry.path();
+            path.extend(bin_path);
+            path.push(NODE_BINARY);
+            path.exists().then_some((version, path))
+        })
+        .max_by(|a, b| a.0.cmp(&b.0))
+        .map(|(_, path)| path)
+}
+
+/// 通过 `which`（Windows 上为 `where`）查找 node
+fn find_node_on_command_path() -> Option<PathBuf> {
+    let finder = if cfg!(target_os = "windows") { "where" } else { "which" };
+    let output = Command::new(finder).arg("node").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    // where 可能返回多行，取第一条
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let path = PathBuf::from(stdout.lines().next()?.trim());
+    path.exists().then_some(path)
+}
+
+/// 在 nvm、fnm、Volta 等版本管理器的安装目录中查找 node
+fn find_node_in_version_managers() -> Option<PathBuf> {
+    let home = dirs::home_dir()?;
+
+    // Volta
+    let volta = home.join(".volta").join("bin").join(NODE_BINARY);
+    if volta.exists() {
+        return Some(volta);
+    }
+
+    // nvm：~/.nvm/versions/node/<version>/bin/node
+    let nvm_dir = std::env::var_os("NVM_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home.join(".nvm"));
+    if let Some(path) = highest_version_node(&nvm_dir.join("versions").join("node"), &["bin"]) {
+        return Some(path);
+    }
+
+    // fnm：优先默认别名，其次最高版本
+    let mut fnm_dirs: Vec<PathBuf> = std::env::var_os("FNM_DIR")
+        .map(PathBuf::from)
+        .into_iter()
+        .collect();
+    fnm_dirs.push(home.join(".local").join("share").join("fnm"));
+    fnm_dirs.push(home.join("Library").join("Application Support").join("fnm"));
+    fnm_dirs.push(home.join(".fnm"));
+    for fnm_dir in fnm_dirs {
+        let default_alias = fnm_dir.join("aliases").join("default").join("bin").join(NODE_BINARY);
+        if default_alias.exists() {
+            return Some(default_alias);
+        }
+        let versions = fnm_dir.join("node-versions");
+        if let Some(path) = highest_version_node(&versions, &["installation", "bin"]) {
+            return Some(path);
         }
     }
+
+    None
+}
+
+/// 运行 `<path> --version` 并返回版本号，用于校验 Node.js 路径
+fn node_version(path: &Path) -> Result<String, String> {
+    let output = Command::new(path)
+        .arg("--version")
+        .output()
+        .map_err(|e| format!("无法执行 {}: {}", path.display(), e))?;
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || !version.starts_with('v') {
+        return Err(format!("{} 不是有效的 Node.js 可执行文件", path.display()));
+    }
+    Ok(version)
+}
+
+/// 设置自定义 Node.js 路径（传空则恢复自动查找），返回该 Node.js 的版本号
+#[tauri::command]
+pub async fn set_node_path(path: Option<String>) -> Result<Option<String>, String> {
+    let path = path.map(|p| p.trim().to_string()).filter(|p| !p.is_empty());
+    let version = match &path {
+        Some(path) => Some(node_version(Path::new(path))?),
+        None => None,
+    };
+
+    let mut settings = crate::settings::get_settings();
+    settings.node_path = path;
+    crate::settings::update_settings(settings)?;
+    Ok(version)
 }
 
 /// 查找 Node.js 可执行文件
 fn find_node_executable() -> Option<PathBuf> {
+    // 优先使用设置中指定的路径
+    if let Some(custom) = crate::settings::get_settings().node_path {
+        let path = PathBuf::from(&custom);
+        if path.exists() {
+            return Some(path);
+        }
+        log::warn!("Configured Node.js path not found: {}, falling back to search", custom);
+    }
+    
     // 常见的 Node.js 安装路径
     let common_paths = vec![
         "/usr/local/bin/node",
         "/opt/homebrew/bin/node",
         "/usr/bin/node",
         "/opt/local/bin/node",
-        "~/.nvm/versions/node/current/bin/node",
     ];
     
-    // 首先尝试通过 which 命令查找
-    if let Ok(output) = Command::new("which").arg("node").output() {
-        if output.status.success() {
-            let path_str = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            let path = PathBuf::from(path_str);
-            if path.exists() {
-                return Some(path);
-            }
-        }
+    // 首先尝试通过 which / where 命令查找
+    if let Some(path) = find_node_on_command_path() {
+        return Some(path);
     }
     
     // 然后尝试常见路径
     for path_str in common_paths {
-        let path = if path_str.starts_with('~') {
-            if let Ok(home) = std::env::var("HOME") {
-                PathBuf::from(path_str.replace("~", &home))
-            } else {
-                continue;
-            }
-        } else {
-            PathBuf::from(path_str)
-        };
-        
+        let path = PathBuf::from(path_str);
         if path.exists() {
             return Some(path);
         }
     }
     
+    // 再尝试版本管理器的安装目录（GUI 应用通常拿不到 shell 中的 PATH）
+    if let Some(path) = find_node_in_version_managers() {
+        return Some(path);
+    }
+    
     // 最后尝试从 PATH 环境变量查找
-    if let Ok(path_env) = std::env::var("PATH") {
-        for dir in path_env.split(':') {
-            let node_path = PathBuf::from(dir).join("node");
+    if let Some(path_env) = std::env::var_os("PATH") {
+        for dir in std::env::split_paths(&path_env) {
+            let node_path = dir.join(NODE_BINARY);
             if node_path.exists() {
                 return Some(node_path);
             }
@@ -81,82 +389,313 @@ fn find_node_executable() -> Option<PathBuf> {
     None
 }
 
-#[tauri::command]
-pub async fn start_droid2api_service(
-    service: State<'_, Droid2ApiService>,
-    app_handle: tauri::AppHandle,
-) -> Result<ServiceStatus, String> {
-    let mut process_guard = service.process.lock().map_err(|e| e.to_string())?;
-    let mut status_guard = service.status.lock().map_err(|e| e.to_string())?;
-
-    // 如果服务已经在运行，直接返回状态
-    if status_guard.running {
-        return Ok(status_guard.clone());
-    }
-
-    // 获取 droid2api 目录路径
-    // 在开发模式下，从项目根目录读取；在生产模式下，从资源目录读取
-    let droid2api_dir = if cfg!(debug_assertions) {
-        // 开发模式：使用项目根目录的 droid2api
-        let app_dir = app_handle
-            .path()
-            .app_config_dir()
-            .map_err(|e| format!("Failed to get app directory: {}", e))?;
-        
-        // 向上查找项目根目录（包含 package.json 的目录）
-        let mut current = app_dir.as_path();
-        let mut project_root = None;
-        
-        for _ in 0..10 {
-            if let Some(parent) = current.parent() {
-                if parent.join("package.json").exists() {
-                    project_root = Some(parent.to_path_buf());
-                    break;
-                }
-                current = parent;
-            } else {
-                break;
+/// 从 Node 服务的输出行中解析实际监听的端口
+///
+/// droid2api 启动时会输出 `Starting server on port N...` 和
+/// `Server running on http://localhost:N`。
+fn parse_listening_port(line: &str) -> Option<u16> {
+    let line = line.to_lowercase();
+    for marker in ["localhost:", "port "] {
+        if let Some(idx) = line.find(marker) {
+            let digits: String = line[idx + marker.len()..]
+                .chars()
+                .take_while(|c| c.is_ascii_digit())
+                .collect();
+            if let Ok(port) = digits.parse::<u16>() {
+                return Some(port);
             }
         }
-        
-        match project_root {
-            Some(root) => root.join("droid2api"),
-            None => {
-                // 如果找不到项目根目录，尝试使用相对于可执行文件的路径
-                std::env::current_dir()
-                    .map_err(|e| format!("Failed to get current directory: {}", e))?
-                    .join("droid2api")
+    }
+    None
+}
+
+/// 从 `start` 向上（最多 10 层）查找包含 package.json 的项目根目录
+fn find_project_root(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .skip(1)
+        .take(10)
+        .find(|dir| dir.join("package.json").exists())
+        .map(Path::to_path_buf)
+}
+
+/// Tauri 将 ../droid2api 打包为资源目录下的 _up_/droid2api
+fn bundled_droid2api_dir(resource_dir: &Path) -> PathBuf {
+    resource_dir.join("_up_").join("droid2api")
+}
+
+/// 获取 droid2api 目录路径
+///
+/// 开发模式下从项目根目录读取（找不到时退回当前目录）；生产模式下从资源目录读取，
+/// Windows 上打包路径不存在时退回用户文档目录。
+pub fn resolve_droid2api_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let path = app_handle.path();
+    resolve_droid2api_dir_from(
+        cfg!(debug_assertions),
+        cfg!(target_os = "windows"),
+        path.app_config_dir()
+            .map_err(|e| format!("Failed to get app directory: {}", e)),
+        std::env::current_dir().map_err(|e| format!("Failed to get current directory: {}", e)),
+        path.resource_dir()
+            .map_err(|e| format!("Failed to get resource directory: {}", e)),
+        path.document_dir()
+            .map_err(|e| format!("Failed to get documents directory: {}", e)),
+    )
+}
+
+/// `resolve_droid2api_dir` 的选择逻辑，候选目录由调用方传入（某个目录获取失败时，
+/// 只有真正用到它的分支才会返回该错误）
+fn resolve_droid2api_dir_from(
+    is_dev: bool,
+    documents_fallback: bool,
+    app_config_dir: Result<PathBuf, String>,
+    current_dir: Result<PathBuf, String>,
+    resource_dir: Result<PathBuf, String>,
+    document_dir: Result<PathBuf, String>,
+) -> Result<PathBuf, String> {
+    if is_dev {
+        if let Some(root) = find_project_root(&app_config_dir?) {
+            let dir = root.join("droid2api");
+            log::debug!("droid2api dir (dev, project root): {}", dir.display());
+            return Ok(dir);
+        }
+        let dir = current_dir?.join("droid2api");
+        log::debug!("droid2api dir (dev, current directory): {}", dir.display());
+        return Ok(dir);
+    }
+
+    let bundled_path = bundled_droid2api_dir(&resource_dir?);
+    if documents_fallback && !bundled_path.exists() {
+        log::warn!("Bundled droid2api not found, trying user documents directory");
+        let dir = document_dir?.join("droid2api");
+        log::debug!("droid2api dir (documents): {}", dir.display());
+        return Ok(dir);
+    }
+    log::debug!("droid2api dir (bundled): {}", bundled_path.display());
+    Ok(bundled_path)
+}
+
+#[cfg(target_os = "windows")]
+const NPM_BINARY: &str = "npm.cmd";
+#[cfg(not(target_os = "windows"))]
+const NPM_BINARY: &str = "npm";
+
+/// 首次运行时 droid2api 目录没有 node_modules，用与 node 同目录的 npm 安装依赖
+fn ensure_node_modules(droid2api_dir: &Path, node_path: &Path) -> Result<(), String> {
+    if droid2api_dir.join("node_modules").exists()
+        || !droid2api_dir.join("package.json").exists()
+    {
+        return Ok(());
+    }
+
+    let npm = node_path
+        .parent()
+        .map(|dir| dir.join(NPM_BINARY))
+        .filter(|npm| npm.exists())
+        .unwrap_or_else(|| PathBuf::from(NPM_BINARY));
+    log::info!(
+        "node_modules missing, running {} install in {}",
+        npm.display(),
+        droid2api_dir.display()
+    );
+
+    let output = Command::new(&npm)
+        .arg("install")
+        .current_dir(droid2api_dir)
+        .output()
+        .map_err(|e| format!("Failed to run npm install ({}): {}", npm.display(), e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "npm install failed in {}:\n{}{}",
+            droid2api_dir.display(),
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(())
+}
+
+/// supervisor 检查进程状态的间隔
+const SUPERVISOR_INTERVAL_MS: u64 = 2000;
+/// 自动重启前的等待时间
+const RESTART_DELAY_MS: u64 = 1000;
+
+/// 实例意外退出时发给前端的事件，payload 为 `{ id, port, code }`
+pub const EXITED_EVENT: &str = "droid2api://exited";
+
+/// 后台监控 droid2api 实例：意外退出时通知前端，并按设置自动重启
+pub fn spawn_supervisor(app_handle: &tauri::AppHandle) {
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(std::time::Duration::from_millis(SUPERVISOR_INTERVAL_MS)).await;
+
+            let service = app_handle.state::<Droid2ApiService>();
+            let crashed: Vec<(String, u16, u32, Option<i32>)> = {
+                let Ok(mut instances) = service.instances.lock() else {
+                    continue;
+                };
+                instances
+                    .values_mut()
+                    .filter_map(|instance| {
+                        instance.refresh();
+                        if !std::mem::take(&mut instance.crashed) {
+                            return None;
+                        }
+                        instance.restart_count += 1;
+                        Some((
+                            instance.status.id.clone(),
+                            instance.status.port,
+                            instance.restart_count,
+                            instance.exit_code,
+                        ))
+                    })
+                    .collect()
+            };
+
+            let settings = crate::settings::get_settings();
+            for (id, port, attempt, code) in crashed {
+                let payload = serde_json::json!({ "id": id, "port": port, "code": code });
+                if let Err(e) = app_handle.emit(EXITED_EVENT, payload) {
+                    log::error!("发射 droid2api 退出事件失败: {}", e);
+                }
+
+                if !settings.droid2api_auto_restart {
+                    continue;
+                }
+                if attempt > settings.droid2api_max_restarts {
+                    log::warn!(
+                        "droid2api instance '{}' exited; giving up after {} restarts",
+                        id,
+                        settings.droid2api_max_restarts
+                    );
+                    continue;
+                }
+
+                log::warn!(
+                    "droid2api instance '{}' exited unexpectedly, restarting ({}/{})",
+                    id,
+                    attempt,
+                    settings.droid2api_max_restarts
+                );
+                tokio::time::sleep(std::time::Duration::from_millis(RESTART_DELAY_MS)).await;
+                if let Err(e) = start_instance(&service, &app_handle, &id, Some(port)) {
+                    log::error!("重启 droid2api 实例 '{}' 失败: {}", id, e);
+                }
             }
         }
-    } else {
-        // 生产模式：使用打包的资源目录
-        let resource_dir = app_handle
-            .path()
-            .resource_dir()
-            .map_err(|e| format!("Failed to get resource directory: {}", e))?;
-        
-        // Tauri将 ../droid2api 打包为 _up_/droid2api
-        let bundled_path = resource_dir.join("_up_").join("droid2api");
-        
-        // Windows: 如果打包路径不存在，尝试从用户文档目录读取
-        #[cfg(target_os = "windows")]
-        {
-            if !bundled_path.exists() {
-                log::warn!("Bundled droid2api not found, trying user documents directory");
-                let documents_dir = app_handle
-                    .path()
-                    .document_dir()
-                    .map_err(|e| format!("Failed to get documents directory: {}", e))?;
-                documents_dir.join("droid2api")
+    });
+}
+
+/// 就绪探测参数（来自设置，未设置时使用默认值）
+struct ReadinessProbe {
+    path: String,
+    attempts: u64,
+    interval_ms: u64,
+}
+
+impl ReadinessProbe {
+    const DEFAULT_PATH: &'static str = "/v1/models";
+    const DEFAULT_ATTEMPTS: u64 = 10;
+    const DEFAULT_INTERVAL_MS: u64 = 500;
+
+    fn from_settings() -> Self {
+        let settings = crate::settings::get_settings();
+        let path = settings
+            .droid2api_readiness_path
+            .unwrap_or_else(|| Self::DEFAULT_PATH.to_string());
+        Self {
+            path: if path.starts_with('/') {
+                path
             } else {
-                bundled_path
-            }
+                format!("/{}", path)
+            },
+            attempts: settings
+                .droid2api_readiness_attempts
+                .map_or(Self::DEFAULT_ATTEMPTS, |n| u64::from(n.max(1))),
+            interval_ms: settings
+                .droid2api_readiness_interval_ms
+                .unwrap_or(Self::DEFAULT_INTERVAL_MS)
+                .max(50),
         }
-        #[cfg(not(target_os = "windows"))]
-        {
-            bundled_path
+    }
+}
+
+/// 设置 droid2api 启动时的就绪探测路径、次数与间隔（传 None 恢复默认值）
+#[tauri::command]
+pub async fn set_droid2api_readiness(
+    path: Option<String>,
+    attempts: Option<u32>,
+    interval_ms: Option<u64>,
+) -> Result<(), String> {
+    let mut settings = crate::settings::get_settings();
+    settings.droid2api_readiness_path = path;
+    settings.droid2api_readiness_attempts = attempts;
+    settings.droid2api_readiness_interval_ms = interval_ms;
+    crate::settings::update_settings(settings)
+}
+
+/// 设置 droid2api 意外退出时是否自动重启及最大重启次数
+#[tauri::command]
+pub async fn set_droid2api_autorestart(enabled: bool, max_restarts: u32) -> Result<(), String> {
+    let mut settings = crate::settings::get_settings();
+    settings.droid2api_auto_restart = enabled;
+    settings.droid2api_max_restarts = max_restarts;
+    crate::settings::update_settings(settings)
+}
+
+#[tauri::command]
+pub async fn start_droid2api_service(
+    service: State<'_, Droid2ApiService>,
+    app_handle: tauri::AppHandle,
+    instance_id: Option<String>,
+    port: Option<u16>,
+) -> Result<ServiceStatus, String> {
+    let instance_id = instance_key(instance_id);
+    // 手动启动时重置自动重启计数
+    if let Some(instance) = service
+        .instances
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get_mut(&instance_id)
+    {
+        instance.restart_count = 0;
+    }
+    start_instance(&service, &app_handle, &instance_id, port)
+}
+
+/// 启动指定实例并等待其就绪
+fn start_instance(
+    service: &Droid2ApiService,
+    app_handle: &tauri::AppHandle,
+    instance_id: &str,
+    port: Option<u16>,
+) -> Result<ServiceStatus, String> {
+    let instance_id = instance_id.to_string();
+    {
+        let mut instances = service.instances.lock().map_err(|e| e.to_string())?;
+        let instance = instances
+            .entry(instance_id.clone())
+            .or_insert_with(|| Droid2ApiInstance::new(&instance_id));
+        instance.refresh();
+        instance.crashed = false;
+
+        // 如果服务已经在运行，直接返回状态
+        if instance.status.running {
+            return Ok(instance.status.clone());
         }
-    };
+    }
+
+    // 指定端口时先确认端口空闲
+    if let Some(port) = port {
+        if std::net::TcpListener::bind(("127.0.0.1", port)).is_err() {
+            return Err(format!("port {} already in use", port));
+        }
+    }
+
+    // 解析目录、查找 Node.js 和 npm install 可能耗时较长，不持有实例锁，
+    // 以免阻塞状态查询和 supervisor
+    let droid2api_dir = resolve_droid2api_dir(app_handle)?;
     
     // 检查 droid2api 目录是否存在
     if !droid2api_dir.exists() {
@@ -185,6 +724,24 @@ pub async fn start_droid2api_service(
     
     log::info!("Using Node.js at: {}", node_path.display());
     
+    ensure_node_modules(&droid2api_dir, &node_path)?;
+    
+    let mut instances = service.instances.lock().map_err(|e| e.to_string())?;
+    let instance = instances
+        .entry(instance_id.clone())
+        .or_insert_with(|| Droid2ApiInstance::new(&instance_id));
+    instance.refresh();
+
+    // 安装依赖期间可能已被其他调用启动
+    if instance.status.running {
+        return Ok(instance.status.clone());
+    }
+
+    // 通过 DROID2API_PORT 环境变量把指定端口传给 Node 服务
+    if let Some(port) = port {
+        instance.status.port = port;
+    }
+
     // 启动 Node.js 服务
     let mut command = Command::new(node_path);
     command
@@ -193,47 +750,131 @@ pub async fn start_droid2api_service(
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .env("NODE_ENV", "production");
+    // 未指定端口时清除继承的 DROID2API_PORT，确保使用 config.json 中的端口
+    match port {
+        Some(port) => command.env("DROID2API_PORT", port.to_string()),
+        None => command.env_remove("DROID2API_PORT"),
+    };
     
     // 不传递 FACTORY_API_KEY，让 droid2api 使用客户端请求中的 Authorization header
     // 这样可以支持多用户使用不同的 API Key
 
-    let child = command.spawn().map_err(|e| {
+    let mut child = command.spawn().map_err(|e| {
         format!("Failed to start droid2api service: {}. Make sure Node.js is installed.", e)
     })?;
 
     let pid = child.id();
 
+    // 每次启动重新记录日志
+    let logs = instance.logs.clone();
+    if let Ok(mut logs) = logs.lock() {
+        logs.clear();
+    }
+
+    // 读取 stdout，解析 Node 服务实际绑定的端口（它可能读取了自己的 config.json）
+    let (port_tx, port_rx) = std::sync::mpsc::channel::<u16>();
+    if let Some(stdout) = child.stdout.take() {
+        let logs = logs.clone();
+        std::thread::spawn(move || {
+            let mut port_tx = Some(port_tx);
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                log::debug!("[droid2api] {}", line);
+                if let Some(port) = parse_listening_port(&line) {
+                    if let Some(tx) = port_tx.take() {
+                        let _ = tx.send(port);
+                    }
+                }
+                push_log(&logs, line);
+            }
+        });
+    }
+
+    // 持续读取 stderr，避免管道写满阻塞子进程
+    if let Some(stderr) = child.stderr.take() {
+        let logs = logs.clone();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                log::warn!("[droid2api] {}", line);
+                push_log(&logs, format!("{}{}", STDERR_PREFIX, line));
+            }
+        });
+    }
+
     // 更新状态
-    status_guard.running = true;
-    status_guard.pid = Some(pid);
-    *process_guard = Some(child);
+    instance.status.running = true;
+    instance.status.pid = Some(pid);
+    instance.process = Some(child);
+    let expected_port = instance.status.port;
 
-    log::info!("droid2api service started with PID: {}", pid);
+    log::info!("droid2api instance '{}' started with PID: {}", instance_id, pid);
+    
+    drop(instances); // 释放锁，避免等待期间阻塞其它实例的操作
     
-    // 等待服务器启动（最多等待5秒）
-    let port = status_guard.port;
-    let max_attempts = 10;
+    remember_instance(&instance_id, expected_port, true);
+    
+    // 等待服务器启动（默认最多等待 5 秒，可在设置中调整）
+    let readiness = ReadinessProbe::from_settings();
+    let max_attempts = readiness.attempts;
     let mut attempts = 0;
-    let wait_ms = 500;
+    let wait_ms = readiness.interval_ms;
     
-    log::info!("Waiting for droid2api service to be ready...");
+    // 以 Node 服务输出的实际端口为准
+    let port = match port_rx.recv_timeout(std::time::Duration::from_millis(max_attempts * wait_ms)) {
+        Ok(actual_port) => {
+            if actual_port != expected_port {
+                log::warn!(
+                    "droid2api bound port {} instead of the expected {}",
+                    actual_port,
+                    expected_port
+                );
+            }
+            let mut instances = service.instances.lock().map_err(|e| e.to_string())?;
+            if let Some(instance) = instances.get_mut(&instance_id) {
+                instance.status.port = actual_port;
+            }
+            drop(instances);
+            remember_instance(&instance_id, actual_port, true);
+            actual_port
+        }
+        Err(_) => expected_port,
+    };
     
-    drop(status_guard); // 释放锁以避免死锁
+    log::info!("Waiting for droid2api service to be ready...");
     
     while attempts < max_attempts {
         std::thread::sleep(std::time::Duration::from_millis(wait_ms));
         
+        // 进程提前退出时附带最近的 stderr 输出返回错误
+        let exited = {
+            let mut instances = service.instances.lock().map_err(|e| e.to_string())?;
+            match instances.get_mut(&instance_id) {
+                Some(instance) => {
+                    instance.refresh();
+                    // 启动失败直接报错，不交给 supervisor 重启
+                    instance.crashed = false;
+                    !instance.status.running
+                }
+                None => true,
+            }
+        };
+        if exited {
+            remember_instance(&instance_id, port, false);
+            return Err(with_stderr_tail(
+                "droid2api service exited before becoming ready".to_string(),
+                &logs,
+            ));
+        }
+        
         // 尝试连接到服务器
         let client = reqwest::blocking::Client::new();
         if let Ok(response) = client
-            .get(format!("http://localhost:{}/v1/models", port))
+            .get(format!("http://localhost:{}{}", port, readiness.path))
             .timeout(std::time::Duration::from_secs(2))
             .send()
         {
             if response.status().is_success() {
                 log::info!("droid2api service is ready after {} ms", (attempts + 1) * wait_ms);
-                let status_guard = service.status.lock().map_err(|e| e.to_string())?;
-                return Ok(status_guard.clone());
+                return service.instance_status(&instance_id);
             }
         }
         
@@ -241,22 +882,31 @@ pub async fn start_droid2api_service(
         log::debug!("Waiting for service... attempt {}/{}", attempts, max_attempts);
     }
     
-    log::warn!("droid2api service started but may not be fully ready yet");
-    let status_guard = service.status.lock().map_err(|e| e.to_string())?;
-    Ok(status_guard.clone())
+    // 超时未就绪同样报错并附带最近的 stderr 输出（进程保留运行，便于查看日志）
+    Err(with_stderr_tail(
+        format!(
+            "droid2api service did not become ready within {} ms",
+            max_attempts * wait_ms
+        ),
+        &logs,
+    ))
 }
 
 #[tauri::command]
 pub async fn stop_droid2api_service(
     service: State<'_, Droid2ApiService>,
+    instance_id: Option<String>,
 ) -> Result<ServiceStatus, String> {
-    let mut process_guard = service.process.lock().map_err(|e| e.to_string())?;
-    let mut status_guard = service.status.lock().map_err(|e| e.to_string())?;
+    let instance_id = instance_key(instance_id);
+    let mut instances = service.instances.lock().map_err(|e| e.to_string())?;
+    let instance = instances
+        .get_mut(&instance_id)
+        .ok_or_else(|| format!("droid2api instance '{}' not found", instance_id))?;
 
-    if let Some(mut child) = process_guard.take() {
+    if let Some(mut child) = instance.process.take() {
         match child.kill() {
             Ok(_) => {
-                log::info!("droid2api service stopped");
+                log::info!("droid2api instance '{}' stopped", instance_id);
             }
             Err(e) => {
                 log::warn!("Failed to kill droid2api service: {}", e);
@@ -266,54 +916,77 @@ pub async fn stop_droid2api_service(
     }
 
     // 更新状态
-    status_guard.running = false;
-    status_guard.pid = None;
+    instance.status.running = false;
+    instance.status.pid = None;
+    remember_instance(&instance_id, instance.status.port, false);
 
-    Ok(status_guard.clone())
+    Ok(instance.status.clone())
 }
 
 #[tauri::command]
 pub async fn get_droid2api_service_status(
     service: State<'_, Droid2ApiService>,
+    instance_id: Option<String>,
 ) -> Result<ServiceStatus, String> {
-    let mut process_guard = service.process.lock().map_err(|e| e.to_string())?;
-    let mut status_guard = service.status.lock().map_err(|e| e.to_string())?;
+    let instance_id = instance_key(instance_id);
+    let mut instances = service.instances.lock().map_err(|e| e.to_string())?;
+    let instance = instances
+        .get_mut(&instance_id)
+        .ok_or_else(|| format!("droid2api instance '{}' not found", instance_id))?;
 
     // 检查进程是否还在运行
-    if let Some(child) = process_guard.as_mut() {
-        match child.try_wait() {
-            Ok(Some(_)) => {
-                // 进程已退出
-                status_guard.running = false;
-                status_guard.pid = None;
-                *process_guard = None;
-                log::info!("droid2api service process has exited");
-            }
-            Ok(None) => {
-                // 进程仍在运行
-                status_guard.running = true;
-            }
-            Err(e) => {
-                log::warn!("Failed to check process status: {}", e);
-                status_guard.running = false;
-                status_guard.pid = None;
-                *process_guard = None;
-            }
-        }
-    } else {
-        status_guard.running = false;
-        status_guard.pid = None;
-    }
+    instance.refresh();
 
-    Ok(status_guard.clone())
+    Ok(instance.status.clone())
 }
 
+/// 列出所有 droid2api 实例及其状态（按 id 排序）
 #[tauri::command]
-pub async fn test_droid2api_connection() -> Result<bool, String> {
+pub async fn list_droid2api_instances(
+    service: State<'_, Droid2ApiService>,
+) -> Result<Vec<ServiceStatus>, String> {
+    let mut instances = service.instances.lock().map_err(|e| e.to_string())?;
+    let mut statuses: Vec<ServiceStatus> = instances
+        .values_mut()
+        .map(|instance| {
+            instance.refresh();
+            instance.status.clone()
+        })
+        .collect();
+    statuses.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(statuses)
+}
+
+/// 获取实例最近的输出日志（最多 limit 行，stderr 行带有 `[stderr]` 前缀）
+#[tauri::command]
+pub async fn get_droid2api_logs(
+    service: State<'_, Droid2ApiService>,
+    instance_id: Option<String>,
+    limit: usize,
+) -> Result<Vec<String>, String> {
+    let instance_id = instance_key(instance_id);
+    let logs = {
+        let instances = service.instances.lock().map_err(|e| e.to_string())?;
+        instances
+            .get(&instance_id)
+            .map(|instance| instance.logs.clone())
+            .ok_or_else(|| format!("droid2api instance '{}' not found", instance_id))?
+    };
+    let logs = logs.lock().map_err(|e| e.to_string())?;
+    let skip = logs.len().saturating_sub(limit);
+    Ok(logs.iter().skip(skip).cloned().collect())
+}
+
+#[tauri::command]
+pub async fn test_droid2api_connection(port: Option<u16>) -> Result<bool, String> {
     let client = reqwest::Client::new();
     
     match client
-        .get("http://localhost:3000/v1/models")
+        .get(format!(
+            "http://localhost:{}{}",
+            port.unwrap_or(3000),
+            ReadinessProbe::from_settings().path
+        ))
         .timeout(std::time::Duration::from_secs(5))
         .send()
         .await
@@ -321,4 +994,131 @@ pub async fn test_droid2api_connection() -> Result<bool, String> {
         Ok(response) => Ok(response.status().is_success()),
         Err(_) => Ok(false),
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 在系统临时目录下创建一个独立的测试目录
+    fn temp_base(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "cc-switch-{}-{}-{}",
+            name,
+            std::process::id(),
+            crate::droid_config::now_millis()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn finds_project_root_within_ten_levels() {
+        let base = temp_base("root-found");
+        fs::write(base.join("package.json"), "{}").unwrap();
+        let start = base.join("a").join("b").join("c");
+        fs::create_dir_all(&start).unwrap();
+
+        assert_eq!(find_project_root(&start), Some(base.clone()));
+        fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    fn ignores_project_root_more_than_ten_levels_up() {
+        let base = temp_base("root-too-deep");
+        fs::write(base.join("package.json"), "{}").unwrap();
+        let start = (1..=11).fold(base.clone(), |dir, level| dir.join(format!("l{}", level)));
+        fs::create_dir_all(&start).unwrap();
+
+        assert_eq!(find_project_root(&start), None);
+        fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    fn dev_mode_uses_project_root() {
+        let base = temp_base("dev-root");
+        fs::write(base.join("package.json"), "{}").unwrap();
+        let app_config_dir = base.join("config").join("cc-switch");
+        fs::create_dir_all(&app_config_dir).unwrap();
+
+        let dir = resolve_droid2api_dir_from(
+            true,
+            false,
+            Ok(app_config_dir),
+            Err("unused".to_string()),
+            Err("unused".to_string()),
+            Err("unused".to_string()),
+        );
+        assert_eq!(dir, Ok(base.join("droid2api")));
+        fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    fn dev_mode_falls_back_to_current_dir() {
+        let base = temp_base("dev-cwd");
+        let app_config_dir =
+            (1..=11).fold(base.clone(), |dir, level| dir.join(format!("l{}", level)));
+        fs::create_dir_all(&app_config_dir).unwrap();
+        let cwd = PathBuf::from("/work/cc-switch");
+
+        let dir = resolve_droid2api_dir_from(
+            true,
+            false,
+            Ok(app_config_dir),
+            Ok(cwd.clone()),
+            Err("unused".to_string()),
+            Err("unused".to_string()),
+        );
+        assert_eq!(dir, Ok(cwd.join("droid2api")));
+        fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    fn production_uses_bundled_dir() {
+        let resource_dir = temp_base("bundled");
+        let bundled = resource_dir.join("_up_").join("droid2api");
+        fs::create_dir_all(&bundled).unwrap();
+
+        // 打包目录存在时，Windows 也不退回文档目录
+        for documents_fallback in [false, true] {
+            let dir = resolve_droid2api_dir_from(
+                false,
+                documents_fallback,
+                Err("unused".to_string()),
+                Err("unused".to_string()),
+                Ok(resource_dir.clone()),
+                Ok(PathBuf::from("/home/user/Documents")),
+            );
+            assert_eq!(dir, Ok(bundled.clone()));
+        }
+        fs::remove_dir_all(&resource_dir).unwrap();
+    }
+
+    #[test]
+    fn windows_falls_back_to_documents_when_bundle_missing() {
+        let resource_dir = temp_base("bundle-missing");
+        let documents = PathBuf::from("C:/Users/user/Documents");
+
+        let dir = resolve_droid2api_dir_from(
+            false,
+            true,
+            Err("unused".to_string()),
+            Err("unused".to_string()),
+            Ok(resource_dir.clone()),
+            Ok(documents.clone()),
+        );
+        assert_eq!(dir, Ok(documents.join("droid2api")));
+
+        // 其他平台不退回文档目录
+        let dir = resolve_droid2api_dir_from(
+            false,
+            false,
+            Err("unused".to_string()),
+            Err("unused".to_string()),
+            Ok(resource_dir.clone()),
+            Ok(documents),
+        );
+        assert_eq!(dir, Ok(resource_dir.join("_up_").join("droid2api")));
+        fs::remove_dir_all(&resource_dir).unwrap();
+    }
+}
@@ -15,6 +15,17 @@ pub struct DroidCustomModel {
     pub max_tokens: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub supports_prompt_caching: Option<bool>,
+    /// 由 cc-switch 写入的模型带有此标记，切换时只替换这些模型
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cc_switch_managed: Option<bool>,
+}
+
+/// Factory 自定义模型及其来源
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FactoryModelInfo {
+    #[serde(flatten)]
+    pub model: DroidCustomModel,
+    pub managed_by_cc_switch: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +49,9 @@ pub struct ApiKeyInfo {
     pub is_active: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub last_used: Option<i64>,
+    /// 被选为当前 Key 的次数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_count: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub balance: Option<KeyBalance>,
 }
@@ -94,6 +108,561 @@ pub struct DroidProvider {
     pub supports_prompt_caching: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub created_at: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_invalid: Option<bool>, // 所有 Key 均鉴权失败
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>, // 分组标签
+}
+
+/// Case-insensitive substring search over name, model, display name and base URL
+///
+/// Matches keep their order in `providers`; an empty query returns every provider.
+pub fn search_providers(providers: &[DroidProvider], query: &str) -> Vec<DroidProvider> {
+    let query = query.trim().to_lowercase();
+    providers
+        .iter()
+        .filter(|p| {
+            query.is_empty()
+                || [
+                    Some(p.name.as_str()),
+                    p.model.as_deref(),
+                    p.model_display_name.as_deref(),
+                    p.base_url.as_deref(),
+                ]
+                .into_iter()
+                .flatten()
+                .any(|field| field.to_lowercase().contains(&query))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Providers carrying `tag` (case-insensitive), in list order
+pub fn list_providers_by_tag(providers: &[DroidProvider], tag: &str) -> Vec<DroidProvider> {
+    let tag = tag.trim();
+    providers
+        .iter()
+        .filter(|p| {
+            p.tags
+                .iter()
+                .flatten()
+                .any(|t| t.trim().eq_ignore_ascii_case(tag))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Find the next active key index after `start`, wrapping around (`start` itself is checked last)
+pub fn next_active_key_index(keys: &[ApiKeyInfo], start: usize) -> Option<usize> {
+    let len = keys.len();
+    (1..=len)
+        .map(|offset| (start + offset) % len)
+        .find(|&i| keys[i].is_active)
+}
+
+/// Pick the key index the provider's switch strategy would move to next
+///
+/// Returns `None` when the provider has no API keys.
+pub fn select_next_key_index(provider: &DroidProvider) -> Option<usize> {
+    let api_keys = provider.api_keys.as_ref().filter(|keys| !keys.is_empty())?;
+    let current_index = provider.current_key_index.unwrap_or(0);
+    let next_index = match provider.switch_strategy.as_ref().unwrap_or(&SwitchStrategy::Manual) {
+        SwitchStrategy::RoundRobin => {
+            // 轮询策略（跳过已停用的 Key）
+            next_active_key_index(api_keys, current_index)?
+        }
+        SwitchStrategy::UseLowest | SwitchStrategy::UseHighest => {
+            // 按余额选择；尚无余额数据时保持当前 Key
+            let strategy = provider.switch_strategy.as_ref().unwrap_or(&SwitchStrategy::Manual);
+            select_key_by_balance(provider, strategy).unwrap_or(current_index)
+        }
+        SwitchStrategy::Manual => current_index, // Manual模式不自动切换
+    };
+    Some(next_index)
+}
+
+/// Make the key at `index` the provider's current key and record the use
+///
+/// Keeps `api_key` in sync, stamps `last_used` and bumps `request_count`. Does nothing
+/// when the index is out of range.
+pub fn select_key(provider: &mut DroidProvider, index: usize) {
+    let Some(key) = provider.api_keys.as_mut().and_then(|keys| keys.get_mut(index)) else {
+        return;
+    };
+    key.last_used = Some(now_millis());
+    key.request_count = Some(key.request_count.unwrap_or(0) + 1);
+    provider.api_key = key.key.clone();
+    provider.current_key_index = Some(index);
+}
+
+/// Pick the active key with the lowest (`UseLowest`) or highest (`UseHighest`) remaining balance
+///
+/// Keys that are disabled (`is_active == false`) or have no balance yet are skipped; ties go to the least recently
+/// used key (never-used keys first), then to the lower index.
+/// Returns `None` for other strategies or when no key qualifies.
+pub fn select_key_by_balance(provider: &DroidProvider, strategy: &SwitchStrategy) -> Option<usize> {
+    let prefer_lowest = match strategy {
+        SwitchStrategy::UseLowest => true,
+        SwitchStrategy::UseHighest => false,
+        _ => return None,
+    };
+    let mut best: Option<(usize, f64, i64)> = None;
+    for (i, key) in provider.api_keys.as_deref().unwrap_or_default().iter().enumerate() {
+        let Some(balance) = key.balance.as_ref().filter(|_| key.is_active) else {
+            continue;
+        };
+        let last_used = key.last_used.unwrap_or(0);
+        let better = match best {
+            None => true,
+            Some((_, current, current_used)) if balance.remaining == current => {
+                last_used < current_used
+            }
+            Some((_, current, _)) if prefer_lowest => balance.remaining < current,
+            Some((_, current, _)) => balance.remaining > current,
+        };
+        if better {
+            best = Some((i, balance.remaining, last_used));
+        }
+    }
+    best.map(|(i, _, _)| i)
+}
+
+/// Run the switch strategy forward `count` times on a copy of the provider
+/// and return the key indices it would use, without touching any state
+pub fn simulate_rotation(provider: &DroidProvider, count: usize) -> Result<Vec<usize>, String> {
+    let mut simulated = provider.clone();
+    let mut sequence = Vec::with_capacity(count);
+    for _ in 0..count {
+        let next_index =
+            select_next_key_index(&simulated).ok_or_else(|| "没有可用的API Key".to_string())?;
+        select_key(&mut simulated, next_index);
+        sequence.push(next_index);
+    }
+    Ok(sequence)
+}
+
+/// Update the label and/or active flag of one API key of a provider
+///
+/// If the currently selected key gets deactivated, the selection moves to the
+/// next active key and `api_key` is kept in sync.
+pub fn update_api_key(
+    provider: &mut DroidProvider,
+    key_id: &str,
+    name: Option<String>,
+    is_active: Option<bool>,
+) -> Result<(), String> {
+    let keys = provider
+        .api_keys
+        .as_mut()
+        .ok_or_else(|| format!("Provider {} 没有配置多个 API Key", provider.id))?;
+    let index = keys
+        .iter()
+        .position(|k| k.id == key_id)
+        .ok_or_else(|| format!("API Key {} 不存在", key_id))?;
+
+    if let Some(name) = name {
+        let name = name.trim().to_string();
+        keys[index].name = if name.is_empty() { None } else { Some(name) };
+    }
+    if let Some(is_active) = is_active {
+        keys[index].is_active = is_active;
+    }
+
+    reselect_if_current_disabled(provider);
+    Ok(())
+}
+
+/// Move the selection to the next active key when the current key is disabled
+///
+/// Keeps `api_key` in sync. Returns whether the selection changed.
+pub fn reselect_if_current_disabled(provider: &mut DroidProvider) -> bool {
+    let Some(keys) = provider.api_keys.as_ref() else {
+        return false;
+    };
+    let current = provider.current_key_index.unwrap_or(0);
+    if !matches!(keys.get(current), Some(key) if !key.is_active) {
+        return false;
+    }
+    match next_active_key_index(keys, current) {
+        Some(next) if next != current => {
+            select_key(provider, next);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Disable a key that upstream rejected with 401 and promote the next active one
+///
+/// Returns the id of the newly active key, or `None` (with `is_invalid` set) when
+/// no active key is left.
+pub fn handle_auth_failure(
+    provider: &mut DroidProvider,
+    key_id: &str,
+) -> Result<Option<String>, String> {
+    let keys = provider
+        .api_keys
+        .as_mut()
+        .ok_or_else(|| format!("Provider {} 没有配置多个 API Key", provider.id))?;
+    let index = keys
+        .iter()
+        .position(|k| k.id == key_id)
+        .ok_or_else(|| format!("API Key {} 不存在", key_id))?;
+    keys[index].is_active = false;
+
+    match next_active_key_index(keys, index) {
+        Some(next) => {
+            let next_id = keys[next].id.clone();
+            select_key(provider, next);
+            provider.is_invalid = None;
+            Ok(Some(next_id))
+        }
+        None => {
+            provider.is_invalid = Some(true);
+            Ok(None)
+        }
+    }
+}
+
+/// Factory 用量查询接口（同时用于校验 API Key 是否有效）
+const FACTORY_USAGE_URL: &str = "https://app.factory.ai/api/organization/members/chat-usage";
+
+/// 使用指定 API Key 请求 Factory 用量接口，返回原始响应
+pub async fn send_usage_request(api_key: &str) -> Result<reqwest::Response, String> {
+    use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, USER_AGENT, ACCEPT};
+    
+    // 构建请求头
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        AUTHORIZATION,
+        HeaderValue::from_str(&format!("Bearer {}", api_key))
+            .map_err(|e| format!("Invalid API key format: {}", e))?
+    );
+    headers.insert(
+        USER_AGENT,
+        HeaderValue::from_static("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/141.0.0.0 Safari/537.36")
+    );
+    headers.insert(
+        ACCEPT,
+        HeaderValue::from_static("*/*")
+    );
+    
+    // 创建HTTP客户端
+    let client = reqwest::Client::builder()
+        .default_headers(headers)
+        .timeout(std::time::Duration::from_secs(30))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    
+    // 发送请求
+    client
+        .get(FACTORY_USAGE_URL)
+        .send()
+        .await
+        .map_err(|e| {
+            if e.is_timeout() {
+                "Request timeout after 30 seconds".to_string()
+            } else if e.is_connect() {
+                format!("Connection failed: {}", e)
+            } else {
+                format!("Request failed: {}", e)
+            }
+        })
+}
+
+/// 查询单个 Key 的用量，401 时返回 `None`
+async fn fetch_key_usage(api_key: &str) -> Result<Option<serde_json::Value>, String> {
+    let response = send_usage_request(api_key).await?;
+    let status = response.status();
+    if status == reqwest::StatusCode::UNAUTHORIZED {
+        return Ok(None);
+    }
+    if !status.is_success() {
+        return Err(format!("API returned error {}", status));
+    }
+    response
+        .json::<serde_json::Value>()
+        .await
+        .map(Some)
+        .map_err(|e| format!("Failed to parse response: {}", e))
+}
+
+/// Build a `KeyBalance` from a Factory usage response (same fallbacks as the key editor)
+pub fn parse_key_balance(data: &serde_json::Value, now_ms: i64) -> KeyBalance {
+    const DEFAULT_ALLOWANCE: f64 = 20_000_000.0;
+    let first_number = |paths: &[&str]| {
+        paths
+            .iter()
+            .filter_map(|p| data.pointer(p).and_then(|v| v.as_f64()))
+            .find(|v| *v != 0.0)
+    };
+
+    let (total_allowance, total_used) = if data.pointer("/usage/standard").is_some() {
+        (
+            first_number(&["/usage/standard/totalAllowance"]).unwrap_or(DEFAULT_ALLOWANCE),
+            first_number(&["/usage/standard/orgTotalTokensUsed"]).unwrap_or(0.0),
+        )
+    } else {
+        (
+            first_number(&["/max_tokens_per_day", "/totalAllowance", "/quotas/tokens_per_day"])
+                .unwrap_or(DEFAULT_ALLOWANCE),
+            first_number(&["/tokens_used_today", "/totalUsed", "/usage/tokens_today"])
+                .unwrap_or(0.0),
+        )
+    };
+
+    KeyBalance {
+        total_allowance,
+        total_used,
+        remaining: total_allowance - total_used,
+        used_ratio: if total_allowance > 0.0 { total_used / total_allowance } else { 0.0 },
+        last_checked: Some(now_ms),
+    }
+}
+
+/// Default age after which a cached balance is refreshed again
+pub const DEFAULT_BALANCE_MAX_AGE_SECS: u64 = 300;
+
+/// Refresh the cached balance of the provider's keys checked more than `max_age_secs` ago
+///
+/// Keys rejected with 401 are marked inactive; other failures are logged and the old
+/// balance is kept. Returns how many balances were updated.
+pub async fn refresh_balances(
+    provider: &mut DroidProvider,
+    max_age_secs: u64,
+) -> Result<usize, String> {
+    let Some(keys) = provider.api_keys.as_mut() else {
+        return Ok(0);
+    };
+    let now_ms = now_millis();
+    let mut stale: Vec<&mut ApiKeyInfo> = keys
+        .iter_mut()
+        .filter(|key| is_balance_stale(key, max_age_secs, now_ms))
+        .collect();
+    let results =
+        futures::future::join_all(stale.iter().map(|key| fetch_key_usage(&key.key))).await;
+
+    let now_ms = now_millis();
+    let mut refreshed = 0;
+    for (key, result) in stale.iter_mut().zip(results) {
+        match result {
+            Ok(Some(data)) => {
+                key.balance = Some(parse_key_balance(&data, now_ms));
+                refreshed += 1;
+            }
+            Ok(None) => {
+                log::warn!("API Key {} 鉴权失败，已停用", key.id);
+                key.is_active = false;
+            }
+            Err(e) => log::warn!("刷新 API Key {} 余额失败: {}", key.id, e),
+        }
+    }
+    Ok(refreshed)
+}
+
+/// How many keys `check_all_keys` queries at the same time
+const KEY_CHECK_CONCURRENCY: usize = 5;
+
+/// Check every key of the provider against the Factory usage API
+///
+/// Keys rejected with 401 are deactivated; keys that answer get their balance refreshed
+/// but are never re-enabled, so keys the user disabled stay disabled. Network errors leave
+/// the key unchanged. When the current key gets deactivated the selection moves on like in
+/// `update_api_key`. Returns `(key_id, valid)` for every key, in order.
+pub async fn check_all_keys(provider: &mut DroidProvider) -> Result<Vec<(String, bool)>, String> {
+    use futures::stream::{self, StreamExt};
+
+    let Some(keys) = provider.api_keys.as_mut().filter(|keys| !keys.is_empty()) else {
+        return Ok(Vec::new());
+    };
+    let results: Vec<_> = stream::iter(keys.iter().map(|key| fetch_key_usage(&key.key)))
+        .buffered(KEY_CHECK_CONCURRENCY)
+        .collect()
+        .await;
+
+    let now_ms = now_millis();
+    let mut report = Vec::with_capacity(keys.len());
+    for (key, result) in keys.iter_mut().zip(results) {
+        let valid = match result {
+            Ok(Some(data)) => {
+                key.balance = Some(parse_key_balance(&data, now_ms));
+                true
+            }
+            Ok(None) => {
+                key.is_active = false;
+                false
+            }
+            Err(e) => {
+                log::warn!("检查 API Key {} 失败: {}", key.id, e);
+                key.is_active
+            }
+        };
+        report.push((key.id.clone(), valid));
+    }
+    provider.is_invalid = if report.iter().any(|(_, valid)| *valid) {
+        None
+    } else {
+        Some(true)
+    };
+    reselect_if_current_disabled(provider);
+    Ok(report)
+}
+
+/// Base URL used when a provider doesn't set one
+const DEFAULT_BASE_URL: &str = "https://droid2api-2st1n.sevalla.app";
+
+/// Outcome of a provider connectivity test
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderTestStatus {
+    Ok,
+    Unauthorized,
+    Unreachable,
+    Timeout,
+    Error,
+}
+
+/// Result of `test_provider`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderTestResult {
+    pub status: ProviderTestStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_status: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Send a minimal `GET {base_url}/v1/models` with the provider's key and classify the reply
+///
+/// Listing models costs no tokens and is served by Anthropic- and OpenAI-style endpoints alike.
+pub async fn test_provider(provider: &DroidProvider) -> Result<ProviderTestResult, String> {
+    let base_url = provider.base_url.as_deref().unwrap_or(DEFAULT_BASE_URL);
+    let url = format!("{}/v1/models", base_url.trim_end_matches('/'));
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .build()
+        .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
+
+    let start = std::time::Instant::now();
+    let response = client
+        .get(&url)
+        .bearer_auth(&provider.api_key)
+        .header("x-api-key", &provider.api_key)
+        .header("anthropic-version", "2023-06-01")
+        .send()
+        .await;
+    let latency_ms = Some(start.elapsed().as_millis() as u64);
+
+    let result = match response {
+        Ok(resp) => {
+            let code = resp.status();
+            let status = if code.is_success() {
+                ProviderTestStatus::Ok
+            } else if code == reqwest::StatusCode::UNAUTHORIZED
+                || code == reqwest::StatusCode::FORBIDDEN
+            {
+                ProviderTestStatus::Unauthorized
+            } else {
+                ProviderTestStatus::Error
+            };
+            ProviderTestResult {
+                status,
+                latency_ms,
+                http_status: Some(code.as_u16()),
+                message: None,
+            }
+        }
+        Err(e) => ProviderTestResult {
+            status: if e.is_timeout() {
+                ProviderTestStatus::Timeout
+            } else if e.is_connect() {
+                ProviderTestStatus::Unreachable
+            } else {
+                ProviderTestStatus::Error
+            },
+            latency_ms: None,
+            http_status: None,
+            message: Some(e.to_string()),
+        },
+    };
+    Ok(result)
+}
+
+/// Apply a provider to the Factory config, then test it and roll back if it doesn't answer
+///
+/// The previous `config.json` (or its absence) is restored when the connectivity test
+/// returns anything other than `Ok`.
+pub async fn switch_provider_safe(provider: &DroidProvider) -> Result<(), String> {
+    let config_path = get_factory_config_path()?;
+    let snapshot = if config_path.exists() {
+        Some(
+            fs::read(&config_path)
+                .map_err(|e| format!("读取 Factory 配置文件失败: {}", e))?,
+        )
+    } else {
+        None
+    };
+
+    apply_provider_to_factory(provider, false)?;
+
+    let result = test_provider(provider).await?;
+    if matches!(result.status, ProviderTestStatus::Ok) {
+        return Ok(());
+    }
+
+    match snapshot {
+        Some(content) => crate::config::atomic_write(&config_path, &content)
+            .map_err(|e| format!("恢复 Factory 配置文件失败: {}", e))?,
+        None => fs::remove_file(&config_path)
+            .map_err(|e| format!("删除 Factory 配置文件失败: {}", e))?,
+    }
+    let detail = result
+        .message
+        .or_else(|| result.http_status.map(|code| format!("HTTP {}", code)))
+        .unwrap_or_default();
+    Err(format!(
+        "Provider {} 连通性测试失败（{:?} {}），已恢复原 Factory 配置",
+        provider.name, result.status, detail
+    ))
+}
+
+/// Current unix time in milliseconds (same unit the frontend stores in `last_checked`)
+pub fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// Whether a key's balance is missing or was checked more than `max_age_secs` ago
+pub fn is_balance_stale(key: &ApiKeyInfo, max_age_secs: u64, now_ms: i64) -> bool {
+    match key.balance.as_ref().and_then(|b| b.last_checked) {
+        Some(last_checked) => now_ms.saturating_sub(last_checked) > (max_age_secs as i64) * 1000,
+        None => true,
+    }
+}
+
+/// Collect `(provider_id, key_id)` for every key whose cached balance is stale
+///
+/// Key ids are only unique within one provider (duplicated providers keep them), so the
+/// provider id is returned alongside.
+pub fn find_stale_balances(
+    providers: &[DroidProvider],
+    max_age_secs: u64,
+) -> Vec<(String, String)> {
+    let now_ms = now_millis();
+    providers
+        .iter()
+        .flat_map(|p| {
+            p.api_keys
+                .iter()
+                .flatten()
+                .filter(move |key| is_balance_stale(key, max_age_secs, now_ms))
+                .map(move |key| (p.id.clone(), key.id.clone()))
+        })
+        .collect()
 }
 
 /// Get the Factory config directory path
@@ -102,6 +671,37 @@ pub fn get_factory_config_dir() -> Result<PathBuf, String> {
     Ok(home_dir.join(".factory"))
 }
 
+/// Get the Factory sessions directory path
+pub fn get_factory_sessions_dir() -> Result<PathBuf, String> {
+    Ok(get_factory_config_dir()?.join("sessions"))
+}
+
+/// Delete a Droid session (`<id>.jsonl` and its `<id>.settings.json`)
+pub fn delete_droid_session(session_id: &str) -> Result<(), String> {
+    let session_id = session_id.trim();
+    if session_id.is_empty()
+        || session_id.starts_with('.')
+        || session_id.contains(['/', '\\'])
+        || session_id.contains("..")
+    {
+        return Err(format!("无效的会话 ID: {}", session_id));
+    }
+    
+    let sessions_dir = get_factory_sessions_dir()?;
+    let session_path = sessions_dir.join(format!("{}.jsonl", session_id));
+    if !session_path.exists() {
+        return Err(format!("会话 {} 不存在", session_id));
+    }
+    fs::remove_file(&session_path).map_err(|e| format!("删除会话文件失败: {}", e))?;
+    
+    let settings_path = sessions_dir.join(format!("{}.settings.json", session_id));
+    if settings_path.exists() {
+        fs::remove_file(&settings_path)
+            .map_err(|e| format!("删除会话设置文件失败: {}", e))?;
+    }
+    Ok(())
+}
+
 /// Get the Factory config.json file path
 pub fn get_factory_config_path() -> Result<PathBuf, String> {
     let config_dir = get_factory_config_dir()?;
@@ -122,21 +722,38 @@ pub fn get_cc_switch_droid_config_path() -> Result<PathBuf, String> {
     Ok(cc_switch_dir.join("droid_config.json"))
 }
 
-/// Load Droid providers from CC Switch config
-pub fn load_droid_providers() -> Result<Vec<DroidProvider>, String> {
-    let config_path = get_cc_switch_droid_config_path()?;
-    
-    if !config_path.exists() {
-        return Ok(Vec::new());
+/// Providers parsed one entry at a time: the valid ones, the raw JSON of the
+/// malformed ones, and an error message for each malformed entry
+#[derive(Debug, Default)]
+pub struct LenientProviders {
+    pub providers: Vec<DroidProvider>,
+    pub invalid: Vec<serde_json::Value>,
+    pub errors: Vec<String>,
+}
+
+/// Parse provider entries element by element so one bad entry doesn't hide the rest
+pub fn parse_providers_lenient(items: Vec<serde_json::Value>) -> LenientProviders {
+    let mut result = LenientProviders {
+        providers: Vec::with_capacity(items.len()),
+        ..Default::default()
+    };
+    for (index, item) in items.into_iter().enumerate() {
+        match serde_json::from_value::<DroidProvider>(item.clone()) {
+            Ok(provider) => result.providers.push(provider),
+            Err(e) => {
+                let label = item
+                    .get("id")
+                    .and_then(|v| v.as_str())
+                    .map(|id| format!("#{} ({})", index, id))
+                    .unwrap_or_else(|| format!("#{}", index));
+                result
+                    .errors
+                    .push(format!("Provider {} 解析失败: {}", label, e));
+                result.invalid.push(item);
+            }
+        }
     }
-    
-    let content = fs::read_to_string(&config_path)
-        .map_err(|e| format!("读取 Droid 配置文件失败: {}", e))?;
-    
-    let providers: Vec<DroidProvider> = serde_json::from_str(&content)
-        .map_err(|e| format!("解析 Droid 配置失败: {}", e))?;
-    
-    Ok(providers)
+    result
 }
 
 /// Save Droid providers to CC Switch config
@@ -146,7 +763,7 @@ pub fn save_droid_providers(providers: &[DroidProvider]) -> Result<(), String> {
     let content = serde_json::to_string_pretty(providers)
         .map_err(|e| format!("序列化 Droid 配置失败: {}", e))?;
     
-    fs::write(&config_path, content)
+    crate::config::atomic_write(&config_path, content.as_bytes())
         .map_err(|e| format!("写入 Droid 配置文件失败: {}", e))?;
     
     Ok(())
@@ -168,7 +785,7 @@ pub fn read_factory_config() -> Result<DroidConfig, String> {
     let content = fs::read_to_string(&config_path)
         .map_err(|e| format!("读取 Factory 配置文件失败: {}", e))?;
     
-    let config: DroidConfig = serde_json::from_str(&content)
+    let config: DroidConfig = serde_json::from_str(crate::config::strip_bom(&content))
         .unwrap_or_else(|_| DroidConfig {
             custom_models: Vec::new(),
             default_model: None,
@@ -179,8 +796,163 @@ pub fn read_factory_config() -> Result<DroidConfig, String> {
     Ok(config)
 }
 
+/// Number of timestamped Factory config backups to keep
+const FACTORY_BACKUP_RETAIN: usize = 5;
+
+/// Factory config backup entry (`~/.factory/config.<ts>.bak`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FactoryBackupInfo {
+    /// Backup time in unix milliseconds
+    pub timestamp: i64,
+    pub path: String,
+    pub size: u64,
+}
+
+/// Parse the timestamp out of a `config.<ts>.bak` file name
+fn parse_factory_backup_ts(file_name: &str) -> Option<i64> {
+    file_name
+        .strip_prefix("config.")?
+        .strip_suffix(".bak")?
+        .parse::<i64>()
+        .ok()
+}
+
+/// List Factory config backups, newest first
+pub fn list_factory_backups() -> Result<Vec<FactoryBackupInfo>, String> {
+    let config_dir = get_factory_config_dir()?;
+    if !config_dir.exists() {
+        return Ok(Vec::new());
+    }
+    
+    let entries = fs::read_dir(&config_dir)
+        .map_err(|e| format!("读取 .factory 目录失败: {}", e))?;
+    let mut backups: Vec<FactoryBackupInfo> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let timestamp = parse_factory_backup_ts(&name)?;
+            Some(FactoryBackupInfo {
+                timestamp,
+                path: entry.path().to_string_lossy().to_string(),
+                size: entry.metadata().map(|m| m.len()).unwrap_or(0),
+            })
+        })
+        .collect();
+    backups.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    Ok(backups)
+}
+
+/// Copy the current Factory config to a timestamped backup and drop old ones
+fn backup_factory_config(config_path: &std::path::Path) -> Result<(), String> {
+    // 旧版只保留单个 config.json.bak，按修改时间改名并入带时间戳的备份，而不是删除
+    let legacy_backup = config_path.with_extension("json.bak");
+    if legacy_backup.exists() {
+        let modified_ms = fs::metadata(&legacy_backup)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or_else(now_millis);
+        let renamed = config_path.with_file_name(format!("config.{}.bak", modified_ms));
+        if !renamed.exists() {
+            if let Err(e) = fs::rename(&legacy_backup, &renamed) {
+                log::warn!("迁移旧的 Factory 备份失败: {}", e);
+            }
+        }
+    }
+    
+    let backup_path = config_path.with_file_name(format!("config.{}.bak", now_millis()));
+    fs::copy(config_path, &backup_path)
+        .map_err(|e| format!("创建备份文件失败: {}", e))?;
+    
+    for old in list_factory_backups()?.into_iter().skip(FACTORY_BACKUP_RETAIN) {
+        if let Err(e) = fs::remove_file(&old.path) {
+            log::warn!("删除旧的 Factory 备份失败: {}: {}", old.path, e);
+        }
+    }
+    Ok(())
+}
+
+/// Restore the Factory config from the backup with the given timestamp
+///
+/// The current config is backed up first, so a restore can itself be undone.
+pub fn restore_factory_backup(timestamp: i64) -> Result<(), String> {
+    let backup = list_factory_backups()?
+        .into_iter()
+        .find(|b| b.timestamp == timestamp)
+        .ok_or_else(|| format!("Factory 备份不存在: {}", timestamp))?;
+    
+    let content = fs::read_to_string(&backup.path)
+        .map_err(|e| format!("读取 Factory 备份失败: {}", e))?;
+    serde_json::from_str::<DroidConfig>(crate::config::strip_bom(&content))
+        .map_err(|e| format!("Factory 备份内容无效: {}", e))?;
+    
+    let config_path = get_factory_config_path()?;
+    if config_path.exists() {
+        backup_factory_config(&config_path)?;
+    }
+    crate::config::atomic_write(&config_path, content.as_bytes())
+        .map_err(|e| format!("写入 Factory 配置文件失败: {}", e))?;
+    Ok(())
+}
+
+/// Check `~/.factory/config.json` for problems that leave the droid CLI confused
+///
+/// Unlike `read_factory_config` this fails on JSON that doesn't match `DroidConfig`.
+/// Returns one message per issue; an empty list means nothing was found.
+pub fn validate_factory_config() -> Result<Vec<String>, String> {
+    let config_path = get_factory_config_path()?;
+    if !config_path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&config_path)
+        .map_err(|e| format!("读取 Factory 配置文件失败: {}", e))?;
+    let config: DroidConfig = serde_json::from_str(crate::config::strip_bom(&content))
+        .map_err(|e| format!("解析 Factory 配置文件失败: {}", e))?;
+
+    let mut issues = Vec::new();
+    let mut seen = std::collections::HashSet::new();
+    for (i, model) in config.custom_models.iter().enumerate() {
+        let label = if model.model_display_name.trim().is_empty() {
+            issues.push(format!("第 {} 个自定义模型缺少 model_display_name", i + 1));
+            format!("#{}", i + 1)
+        } else {
+            model.model_display_name.clone()
+        };
+        if !model.model_display_name.trim().is_empty() && !seen.insert(&model.model_display_name) {
+            issues.push(format!("模型名称重复: {}", label));
+        }
+        if model.model.trim().is_empty() {
+            issues.push(format!("模型 {} 的 model 为空", label));
+        }
+        if model.api_key.trim().is_empty() {
+            issues.push(format!("模型 {} 的 api_key 为空", label));
+        }
+        let base_url = model.base_url.trim();
+        if !(base_url.starts_with("http://") || base_url.starts_with("https://")) {
+            issues.push(format!("模型 {} 的 base_url 无效: '{}'", label, model.base_url));
+        }
+    }
+
+    if let Some(default_model) = config.default_model.as_deref() {
+        let found = config
+            .custom_models
+            .iter()
+            .any(|m| m.model == default_model || m.model_display_name == default_model);
+        if !found {
+            issues.push(format!(
+                "default_model '{}' 不在自定义模型中（若为 Factory 内置模型可忽略）",
+                default_model
+            ));
+        }
+    }
+    Ok(issues)
+}
+
 /// Write Factory config.json
-pub fn write_factory_config(config: &DroidConfig) -> Result<(), String> {
+///
+/// A timestamped backup of the previous file is made first unless `skip_backup` is set.
+pub fn write_factory_config(config: &DroidConfig, skip_backup: bool) -> Result<(), String> {
     let config_path = get_factory_config_path()?;
     let config_dir = get_factory_config_dir()?;
     
@@ -191,16 +963,14 @@ pub fn write_factory_config(config: &DroidConfig) -> Result<(), String> {
     }
     
     // Create backup if file exists
-    if config_path.exists() {
-        let backup_path = config_path.with_extension("json.bak");
-        fs::copy(&config_path, &backup_path)
-            .map_err(|e| format!("创建备份文件失败: {}", e))?;
+    if !skip_backup && config_path.exists() {
+        backup_factory_config(&config_path)?;
     }
     
     let content = serde_json::to_string_pretty(config)
         .map_err(|e| format!("序列化 Factory 配置失败: {}", e))?;
     
-    fs::write(&config_path, content)
+    crate::config::atomic_write(&config_path, content.as_bytes())
         .map_err(|e| format!("写入 Factory 配置文件失败: {}", e))?;
     
     Ok(())
@@ -254,58 +1024,407 @@ pub fn set_current_droid_provider(provider_id: &str) -> Result<(), String> {
     let content = serde_json::to_string_pretty(&state)
         .map_err(|e| format!("序列化 Droid 状态失败: {}", e))?;
     
-    fs::write(&state_file, content)
+    crate::config::atomic_write(&state_file, content.as_bytes())
         .map_err(|e| format!("写入 Droid 状态文件失败: {}", e))?;
     
     Ok(())
 }
 
+/// On-disk shape of `DroidManagerConfig`, before providers are parsed one by one
+#[derive(Deserialize)]
+struct RawDroidManagerConfig {
+    #[serde(default)]
+    providers: Vec<serde_json::Value>,
+    #[serde(default)]
+    current: String,
+    #[serde(default)]
+    invalid_providers: Vec<serde_json::Value>,
+    #[serde(default)]
+    key_flags_migrated: bool,
+    #[serde(default)]
+    legacy_models_adopted: bool,
+}
+
+impl From<RawDroidManagerConfig> for DroidManagerConfig {
+    fn from(raw: RawDroidManagerConfig) -> Self {
+        // 之前解析失败的条目也重新尝试，手动修好后会自动回到 providers
+        let mut items = raw.providers;
+        items.extend(raw.invalid_providers);
+        let mut parsed = parse_providers_lenient(items);
+        for error in &parsed.errors {
+            log::warn!("跳过无效的 Droid Provider: {}", error);
+        }
+        // 旧版界面只把第一个 Key 标为 is_active（当时表示“正在使用”），
+        // 现在 is_active 表示“已启用”，一次性把旧数据中的 Key 全部启用
+        if !raw.key_flags_migrated {
+            for key in parsed
+                .providers
+                .iter_mut()
+                .flat_map(|p| p.api_keys.iter_mut().flatten())
+            {
+                key.is_active = true;
+            }
+        }
+        Self {
+            providers: parsed.providers,
+            current: raw.current,
+            invalid_providers: parsed.invalid,
+            key_flags_migrated: true,
+            legacy_models_adopted: raw.legacy_models_adopted,
+            load_errors: parsed.errors,
+        }
+    }
+}
+
 /// Droid Manager Config for CC Switch
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "RawDroidManagerConfig")]
 pub struct DroidManagerConfig {
     pub providers: Vec<DroidProvider>,
     pub current: String,
+    /// 解析失败的 Provider 原始 JSON，原样写回，避免保存配置时被丢弃
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub invalid_providers: Vec<serde_json::Value>,
+    /// Key 的 is_active 已按“是否启用”的含义迁移
+    pub key_flags_migrated: bool,
+    /// 旧版本写入 Factory 的模型已标记（只在首次启动时执行一次）
+    pub legacy_models_adopted: bool,
+    /// 加载时每个无效 Provider 的错误信息（不持久化）
+    #[serde(skip)]
+    pub load_errors: Vec<String>,
+}
+
+impl Default for DroidManagerConfig {
+    fn default() -> Self {
+        Self {
+            providers: Vec::new(),
+            current: String::new(),
+            invalid_providers: Vec::new(),
+            key_flags_migrated: true,
+            legacy_models_adopted: true,
+            load_errors: Vec::new(),
+        }
+    }
+}
+
+impl DroidManagerConfig {
+    /// Point a `current` id that no longer exists back at the first provider (or clear it)
+    ///
+    /// A provider kept in `invalid_providers` still counts as existing, so fixing the entry
+    /// later restores the selection. Returns whether `current` was changed.
+    pub fn repair_current(&mut self) -> bool {
+        let exists = self.providers.iter().any(|p| p.id == self.current)
+            || self
+                .invalid_providers
+                .iter()
+                .any(|p| p.get("id").and_then(|v| v.as_str()) == Some(self.current.as_str()));
+        if self.current.is_empty() || exists {
+            return false;
+        }
+        let replacement = self.providers.first().map(|p| p.id.clone()).unwrap_or_default();
+        log::warn!(
+            "Droid 当前 Provider '{}' 不存在，已重置为 '{}'",
+            self.current,
+            replacement
+        );
+        self.current = replacement;
+        true
+    }
+
+    /// Copy a provider under a new name and append it
+    ///
+    /// The copy gets a fresh `id` and `created_at`; cached balances and the invalid flag are
+    /// cleared so they are checked again for the new entry.
+    pub fn duplicate_provider(
+        &mut self,
+        provider_id: &str,
+        new_name: &str,
+    ) -> Result<DroidProvider, String> {
+        let new_name = new_name.trim();
+        if new_name.is_empty() {
+            return Err("Provider 名称不能为空".to_string());
+        }
+        let mut copy = self
+            .providers
+            .iter()
+            .find(|p| p.id == provider_id)
+            .cloned()
+            .ok_or_else(|| format!("Provider {} 不存在", provider_id))?;
+
+        let now_ms = now_millis();
+        copy.id = format!("droid-{:x}-{:x}", now_ms, self.providers.len());
+        copy.name = new_name.to_string();
+        copy.created_at = Some(now_ms as u64);
+        copy.is_invalid = None;
+        for key in copy.api_keys.iter_mut().flatten() {
+            key.balance = None;
+        }
+
+        self.providers.push(copy.clone());
+        Ok(copy)
+    }
+}
+
+/// Whether a Factory custom model was written by cc-switch
+pub fn is_cc_switch_managed(model: &DroidCustomModel) -> bool {
+    model.cc_switch_managed == Some(true)
+}
+
+/// List Factory custom models, tagging the ones managed by cc-switch
+pub fn list_factory_models() -> Result<Vec<FactoryModelInfo>, String> {
+    let config = read_factory_config()?;
+    Ok(config
+        .custom_models
+        .into_iter()
+        .map(|model| FactoryModelInfo {
+            managed_by_cc_switch: is_cc_switch_managed(&model),
+            model,
+        })
+        .collect())
+}
+
+/// Display name a provider's model gets in the Factory config
+fn factory_display_name(provider: &DroidProvider) -> String {
+    provider
+        .model_display_name
+        .clone()
+        .unwrap_or_else(|| "Sonnet 4.5 [droid]".to_string())
+}
+
+/// Mark Factory models written by cc-switch before the marker existed
+///
+/// An unmarked model is adopted only when its display name is exactly the one cc-switch
+/// writes for one of `providers` (the API key is not used: the user's own models may share
+/// it). Meant to run once per installation, see `DroidManagerConfig::legacy_models_adopted`.
+/// Returns the number of models marked; the config is only rewritten when it is non-zero.
+pub fn adopt_legacy_factory_models(providers: &[DroidProvider]) -> Result<usize, String> {
+    if providers.is_empty() || !get_factory_config_path()?.exists() {
+        return Ok(0);
+    }
+    let mut config = read_factory_config()?;
+
+    let display_names: Vec<String> = providers.iter().map(factory_display_name).collect();
+    let mut adopted = 0;
+    for model in config
+        .custom_models
+        .iter_mut()
+        .filter(|m| m.cc_switch_managed.is_none())
+    {
+        if display_names.contains(&model.model_display_name) {
+            model.cc_switch_managed = Some(true);
+            adopted += 1;
+        }
+    }
+    if adopted > 0 {
+        write_factory_config(&config, false)?;
+    }
+    Ok(adopted)
 }
 
 /// Apply Droid provider to Factory config
-pub fn apply_provider_to_factory(provider: &DroidProvider) -> Result<(), String> {
+pub fn apply_provider_to_factory(provider: &DroidProvider, skip_backup: bool) -> Result<(), String> {
     // Read existing config
     let mut config = read_factory_config()?;
     
     // Create custom model from provider
     let custom_model = DroidCustomModel {
-        model_display_name: provider.model_display_name.clone()
-            .unwrap_or_else(|| "Sonnet 4.5 [droid]".to_string()),
+        model_display_name: factory_display_name(provider),
         model: provider.model.clone()
             .unwrap_or_else(|| "claude-sonnet-4-5-20250929".to_string()),
         base_url: provider.base_url.clone()
-            .unwrap_or_else(|| "https://droid2api-2st1n.sevalla.app".to_string()),
+            .unwrap_or_else(|| DEFAULT_BASE_URL.to_string()),
         api_key: provider.api_key.clone(),
         provider: provider.provider.clone()
             .unwrap_or_else(|| "anthropic".to_string()),
         max_tokens: provider.max_tokens,
         supports_prompt_caching: provider.supports_prompt_caching,
+        cc_switch_managed: Some(true),
     };
     
-    // Remove all existing droid models first (to avoid duplicates)
-    // 检查是否包含 [droid] 或 [D]
-    config.custom_models.retain(|m| !m.model_display_name.contains("[droid]") && !m.model_display_name.contains("[D]"));
+    // Remove all existing droid models first (to avoid duplicates). Models written before
+    // the marker existed are marked on startup by `adopt_legacy_factory_models`.
+    config.custom_models.retain(|m| !is_cc_switch_managed(m));
     
     // Add the new model
     config.custom_models.push(custom_model);
     
     // Write config
-    write_factory_config(&config)?;
+    write_factory_config(&config, skip_backup)?;
     
     Ok(())
 }
 
+/// Make an existing Factory custom model the default one
+pub fn set_factory_default_model(model_display_name: &str) -> Result<(), String> {
+    let mut config = read_factory_config()?;
+    if !config
+        .custom_models
+        .iter()
+        .any(|m| m.model_display_name == model_display_name)
+    {
+        return Err(format!("模型 {} 不存在", model_display_name));
+    }
+    config.default_model = Some(model_display_name.to_string());
+    write_factory_config(&config, false)
+}
+
 /// Remove old Factory model by display name
 pub fn remove_old_factory_model(old_display_name: &Option<String>) -> Result<(), String> {
     if let Some(display_name) = old_display_name {
         let mut config = read_factory_config()?;
         config.custom_models.retain(|m| m.model_display_name != *display_name);
-        write_factory_config(&config)?;
+        write_factory_config(&config, false)?;
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A stored manager whose provider's keys were created by the key editor:
+    /// before the fix only the first key got `is_active: true`
+    fn ui_manager(strategy: &str, migrated: bool) -> DroidManagerConfig {
+        let keys: Vec<serde_json::Value> = (0..3)
+            .map(|i| {
+                serde_json::json!({
+                    "id": format!("17000000000{}", i),
+                    "key": format!("fk-{}", i),
+                    "name": format!("Key {}", i + 1),
+                    "is_active": i == 0,
+                })
+            })
+            .collect();
+        serde_json::from_value(serde_json::json!({
+            "providers": [{
+                "id": "p1",
+                "name": "P1",
+                "api_key": "fk-0",
+                "api_keys": keys,
+                "current_key_index": 0,
+                "switch_strategy": strategy,
+            }],
+            "current": "p1",
+            "key_flags_migrated": migrated,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn round_robin_cycles_through_keys_created_by_ui() {
+        let manager = ui_manager("round_robin", false);
+        let provider = &manager.providers[0];
+        assert_eq!(simulate_rotation(provider, 4).unwrap(), vec![1, 2, 0, 1]);
+    }
+
+    #[test]
+    fn balance_strategies_consider_every_key_created_by_ui() {
+        let mut manager = ui_manager("use_lowest", false);
+        let provider = &mut manager.providers[0];
+        for (key, remaining) in provider.api_keys.iter_mut().flatten().zip([500.0, 100.0, 900.0]) {
+            key.balance = Some(KeyBalance {
+                total_allowance: 1000.0,
+                total_used: 1000.0 - remaining,
+                remaining,
+                used_ratio: (1000.0 - remaining) / 1000.0,
+                last_checked: Some(0),
+            });
+        }
+        assert_eq!(select_key_by_balance(provider, &SwitchStrategy::UseLowest), Some(1));
+        assert_eq!(select_key_by_balance(provider, &SwitchStrategy::UseHighest), Some(2));
+    }
+
+    #[test]
+    fn auth_failure_promotes_next_key_created_by_ui() {
+        let mut manager = ui_manager("manual", false);
+        let provider = &mut manager.providers[0];
+
+        let next = handle_auth_failure(provider, "170000000000").unwrap();
+        assert_eq!(next.as_deref(), Some("170000000001"));
+        assert_eq!(provider.current_key_index, Some(1));
+        assert_eq!(provider.api_key, "fk-1");
+        assert_eq!(provider.is_invalid, None);
+
+        assert!(handle_auth_failure(provider, "170000000001").unwrap().is_some());
+        assert_eq!(handle_auth_failure(provider, "170000000002").unwrap(), None);
+        assert_eq!(provider.is_invalid, Some(true));
+    }
+
+    #[test]
+    fn stale_balances_name_the_provider() {
+        let mut manager = ui_manager("manual", true);
+        let copy = manager.duplicate_provider("p1", "P1 copy").unwrap();
+
+        let stale = find_stale_balances(&manager.providers, 60);
+        assert_eq!(stale.len(), 6);
+        assert!(stale.contains(&("p1".to_string(), "170000000000".to_string())));
+        assert!(stale.contains(&(copy.id, "170000000000".to_string())));
+    }
+
+    #[test]
+    fn key_flag_migration_runs_once() {
+        let mut manager = ui_manager("round_robin", false);
+        update_api_key(&mut manager.providers[0], "170000000001", None, Some(false)).unwrap();
+
+        let saved = serde_json::to_value(&manager).unwrap();
+        assert_eq!(saved["key_flags_migrated"], true);
+        let reloaded: DroidManagerConfig = serde_json::from_value(saved).unwrap();
+        let provider = &reloaded.providers[0];
+        assert_eq!(simulate_rotation(provider, 3).unwrap(), vec![2, 0, 2]);
+    }
+
+    #[test]
+    fn invalid_providers_survive_round_trip() {
+        let raw = serde_json::json!({
+            "providers": [
+                { "id": "ok", "name": "OK", "api_key": "sk-1" },
+                { "id": "bad", "name": 42 }
+            ],
+            "current": "ok"
+        });
+        let manager: DroidManagerConfig = serde_json::from_value(raw).unwrap();
+        assert_eq!(manager.providers.len(), 1);
+        assert_eq!(manager.invalid_providers.len(), 1);
+        assert_eq!(manager.load_errors.len(), 1);
+        assert!(manager.load_errors[0].contains("bad"));
+
+        let saved = serde_json::to_value(&manager).unwrap();
+        assert_eq!(saved["invalid_providers"][0]["id"], "bad");
+        assert!(saved.get("load_errors").is_none());
+
+        let reloaded: DroidManagerConfig = serde_json::from_value(saved).unwrap();
+        assert_eq!(reloaded.providers.len(), 1);
+        assert_eq!(reloaded.invalid_providers.len(), 1);
+    }
+
+    #[test]
+    fn repair_current_keeps_invalid_current_provider() {
+        let mut manager: DroidManagerConfig = serde_json::from_value(serde_json::json!({
+            "providers": [
+                { "id": "ok", "name": "OK", "api_key": "sk-1" },
+                { "id": "bad", "name": 42 }
+            ],
+            "current": "bad"
+        }))
+        .unwrap();
+        assert!(!manager.repair_current());
+        assert_eq!(manager.current, "bad");
+
+        manager.current = "gone".to_string();
+        assert!(manager.repair_current());
+        assert_eq!(manager.current, "ok");
+    }
+
+    #[test]
+    fn repaired_invalid_provider_is_restored() {
+        let raw = serde_json::json!({
+            "providers": [],
+            "current": "",
+            "invalid_providers": [{ "id": "fixed", "name": "Fixed", "api_key": "sk-2" }]
+        });
+        let manager: DroidManagerConfig = serde_json::from_value(raw).unwrap();
+        assert_eq!(manager.providers.len(), 1);
+        assert!(manager.invalid_providers.is_empty());
+        assert!(manager.load_errors.is_empty());
+    }
+}
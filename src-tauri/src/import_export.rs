@@ -1,10 +1,14 @@
 use chrono::Utc;
+use serde::Serialize;
 use serde_json::{json, Value};
 use std::fs;
 use std::path::PathBuf;
 
 // 默认仅保留最近 10 份备份，避免目录无限膨胀
 const MAX_BACKUPS: usize = 10;
+// 定时备份的最小间隔，以及未开启时重新检查设置的间隔
+const MIN_BACKUP_INTERVAL_SECS: u64 = 60;
+const BACKUP_SCHEDULE_IDLE_SECS: u64 = 60;
 
 /// 创建配置文件备份
 pub fn create_backup(config_path: &PathBuf) -> Result<String, String> {
@@ -29,17 +33,24 @@ pub fn create_backup(config_path: &PathBuf) -> Result<String, String> {
     // 复制配置文件到备份
     fs::copy(config_path, backup_path).map_err(|e| format!("Failed to create backup: {}", e))?;
 
-    // 备份完成后清理旧的备份文件（仅保留最近 MAX_BACKUPS 份）
-    cleanup_old_backups(&backup_dir, MAX_BACKUPS)?;
+    // 备份完成后清理旧的备份文件（默认仅保留最近 MAX_BACKUPS 份，可在设置中调整）
+    let settings = crate::settings::get_settings();
+    cleanup_old_backups(
+        &backup_dir,
+        settings.max_config_backups.unwrap_or(MAX_BACKUPS),
+        settings.max_backup_total_bytes,
+    )?;
 
     Ok(backup_id)
 }
 
-fn cleanup_old_backups(backup_dir: &PathBuf, retain: usize) -> Result<(), String> {
-    if retain == 0 {
-        return Ok(());
-    }
-
+/// 按时间从旧到新删除备份，直到份数不超过 `retain`（0 表示不限制）且总大小不超过
+/// `max_total_bytes`；最新的一份始终保留
+fn cleanup_old_backups(
+    backup_dir: &PathBuf,
+    retain: usize,
+    max_total_bytes: Option<u64>,
+) -> Result<(), String> {
     let mut entries: Vec<_> = match fs::read_dir(backup_dir) {
         Ok(iter) => iter
             .filter_map(|entry| entry.ok())
@@ -54,19 +65,26 @@ fn cleanup_old_backups(backup_dir: &PathBuf, retain: usize) -> Result<(), String
         Err(_) => return Ok(()),
     };
 
-    if entries.len() <= retain {
-        return Ok(());
-    }
-
-    let remove_count = entries.len().saturating_sub(retain);
-
     entries.sort_by(|a, b| {
         let a_time = a.metadata().and_then(|m| m.modified()).ok();
         let b_time = b.metadata().and_then(|m| m.modified()).ok();
         a_time.cmp(&b_time)
     });
 
-    for entry in entries.into_iter().take(remove_count) {
+    let sizes: Vec<u64> = entries
+        .iter()
+        .map(|entry| entry.metadata().map(|m| m.len()).unwrap_or(0))
+        .collect();
+    let mut total: u64 = sizes.iter().sum();
+    let mut remaining = entries.len();
+
+    for (entry, size) in entries.iter().zip(sizes) {
+        let over_count = retain > 0 && remaining > retain;
+        let over_size = max_total_bytes.is_some_and(|max| total > max);
+        if remaining <= 1 || !(over_count || over_size) {
+            break;
+        }
+
         if let Err(err) = fs::remove_file(entry.path()) {
             log::warn!(
                 "Failed to remove old backup {}: {}",
@@ -74,11 +92,158 @@ fn cleanup_old_backups(backup_dir: &PathBuf, retain: usize) -> Result<(), String
                 err
             );
         }
+        total = total.saturating_sub(size);
+        remaining -= 1;
     }
 
     Ok(())
 }
 
+/// 配置备份信息
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+    pub id: String,
+    pub path: String,
+    pub size: u64,
+}
+
+fn get_backup_dir() -> PathBuf {
+    crate::config::get_app_config_dir().join("backups")
+}
+
+/// 校验备份 ID 并返回备份文件路径
+fn backup_path(backup_id: &str) -> Result<PathBuf, String> {
+    let valid = backup_id.starts_with("backup_")
+        && backup_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(format!("Invalid backup id: {}", backup_id));
+    }
+    Ok(get_backup_dir().join(format!("{}.json", backup_id)))
+}
+
+/// 列出配置备份（最新的在前）
+pub fn list_backups() -> Result<Vec<BackupInfo>, String> {
+    let entries = match fs::read_dir(get_backup_dir()) {
+        Ok(iter) => iter,
+        Err(_) => return Ok(Vec::new()),
+    };
+
+    let mut backups: Vec<BackupInfo> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            let id = path.file_stem()?.to_str()?.to_string();
+            if path.extension()? != "json" || !id.starts_with("backup_") {
+                return None;
+            }
+            Some(BackupInfo {
+                id,
+                path: path.to_string_lossy().to_string(),
+                size: entry.metadata().map(|m| m.len()).unwrap_or(0),
+            })
+        })
+        .collect();
+    // 备份 ID 以时间戳命名，按字典序倒排即为时间倒序
+    backups.sort_by(|a, b| b.id.cmp(&a.id));
+    Ok(backups)
+}
+
+/// 删除指定备份
+pub fn delete_backup(backup_id: &str) -> Result<(), String> {
+    let path = backup_path(backup_id)?;
+    if !path.exists() {
+        return Err(format!("Backup not found: {}", backup_id));
+    }
+    fs::remove_file(&path).map_err(|e| format!("Failed to delete backup: {}", e))
+}
+
+/// 仅保留最新的 `keep` 份备份，返回删除的数量
+pub fn prune_backups(keep: usize) -> Result<usize, String> {
+    let mut removed = 0;
+    for backup in list_backups()?.into_iter().skip(keep) {
+        match fs::remove_file(&backup.path) {
+            Ok(()) => removed += 1,
+            Err(err) => log::warn!("Failed to remove backup {}: {}", backup.path, err),
+        }
+    }
+    Ok(removed)
+}
+
+/// 仅当当前配置与最新备份内容不同时创建备份，返回新备份 ID
+fn backup_if_changed() -> Result<Option<String>, String> {
+    let config_path = crate::config::get_app_config_path();
+    if !config_path.exists() {
+        return Ok(None);
+    }
+    let current =
+        fs::read(&config_path).map_err(|e| format!("Failed to read configuration: {}", e))?;
+    if let Some(latest) = list_backups()?.first() {
+        if fs::read(&latest.path).ok().as_deref() == Some(current.as_slice()) {
+            return Ok(None);
+        }
+    }
+    create_backup(&config_path).map(Some)
+}
+
+/// 后台定时备份（间隔由设置 configBackupIntervalSecs 控制，未设置时不备份）
+pub fn spawn_backup_scheduler() {
+    tauri::async_runtime::spawn(async {
+        loop {
+            let Some(interval) = crate::settings::get_settings().config_backup_interval_secs else {
+                tokio::time::sleep(std::time::Duration::from_secs(BACKUP_SCHEDULE_IDLE_SECS))
+                    .await;
+                continue;
+            };
+            tokio::time::sleep(std::time::Duration::from_secs(
+                interval.max(MIN_BACKUP_INTERVAL_SECS),
+            ))
+            .await;
+
+            // 等待期间可能已关闭定时备份
+            if crate::settings::get_settings()
+                .config_backup_interval_secs
+                .is_none()
+            {
+                continue;
+            }
+            match backup_if_changed() {
+                Ok(Some(backup_id)) => log::info!("Scheduled backup created: {}", backup_id),
+                Ok(None) => {}
+                Err(e) => log::warn!("Scheduled backup failed: {}", e),
+            }
+        }
+    });
+}
+
+/// 用新的配置内容替换当前配置（写入前先备份），返回备份 ID
+fn replace_config(content: &str, state: &crate::store::AppState) -> Result<String, String> {
+    // 验证并解析为配置对象
+    let new_config: crate::app_config::MultiAppConfig = serde_json::from_str(content)
+        .map_err(|e| format!("Invalid configuration file: {}", e))?;
+
+    // 备份当前配置
+    let config_path = crate::config::get_app_config_path();
+    let backup_id = create_backup(&config_path)?;
+
+    // 写入新配置到磁盘
+    fs::write(&config_path, content)
+        .map_err(|e| format!("Failed to write configuration: {}", e))?;
+
+    // 更新内存中的状态
+    {
+        let mut config_state = state
+            .config
+            .lock()
+            .map_err(|e| format!("Failed to lock config: {}", e))?;
+        *config_state = new_config;
+    }
+
+    Ok(backup_id)
+}
+
 /// 导出配置文件
 #[tauri::command]
 pub async fn export_config_to_file(file_path: String) -> Result<Value, String> {
@@ -107,31 +272,145 @@ pub async fn import_config_from_file(
     let import_content =
         fs::read_to_string(&file_path).map_err(|e| format!("Failed to read import file: {}", e))?;
 
-    // 验证并解析为配置对象
-    let new_config: crate::app_config::MultiAppConfig = serde_json::from_str(&import_content)
-        .map_err(|e| format!("Invalid configuration file: {}", e))?;
+    let backup_id = replace_config(&import_content, &state)?;
 
-    // 备份当前配置
+    Ok(json!({
+        "success": true,
+        "message": "Configuration imported successfully",
+        "backupId": backup_id
+    }))
+}
+
+/// 导出完整配置（apps/mcp/droid_manager，带格式版本）
+#[tauri::command]
+pub async fn export_config_bundle(
+    file_path: String,
+    state: tauri::State<'_, crate::store::AppState>,
+) -> Result<Value, String> {
+    let config = state
+        .config
+        .lock()
+        .map_err(|e| format!("Failed to lock config: {}", e))?
+        .clone();
+    config.export_to(std::path::Path::new(&file_path))?;
+
+    Ok(json!({
+        "success": true,
+        "message": "Configuration exported successfully",
+        "filePath": file_path
+    }))
+}
+
+/// 导入完整配置（导入前会先备份当前配置）
+#[tauri::command]
+pub async fn import_config_bundle(
+    file_path: String,
+    state: tauri::State<'_, crate::store::AppState>,
+) -> Result<Value, String> {
+    let new_config =
+        crate::app_config::MultiAppConfig::import_from(std::path::Path::new(&file_path))?;
+    let content = serde_json::to_string_pretty(&new_config)
+        .map_err(|e| format!("Failed to serialize configuration: {}", e))?;
+    let backup_id = replace_config(&content, &state)?;
+
+    Ok(json!({
+        "success": true,
+        "message": "Configuration imported successfully",
+        "backupId": backup_id
+    }))
+}
+
+/// 列出配置备份
+#[tauri::command]
+pub async fn list_config_backups() -> Result<Vec<BackupInfo>, String> {
+    list_backups()
+}
+
+/// 立即为当前配置创建一份备份
+#[tauri::command]
+pub async fn create_config_backup() -> Result<BackupInfo, String> {
     let config_path = crate::config::get_app_config_path();
     let backup_id = create_backup(&config_path)?;
+    if backup_id.is_empty() {
+        return Err("Configuration file does not exist, nothing to back up".to_string());
+    }
 
-    // 写入新配置到磁盘
-    fs::write(&config_path, &import_content)
-        .map_err(|e| format!("Failed to write configuration: {}", e))?;
+    let path = backup_path(&backup_id)?;
+    let size = fs::metadata(&path)
+        .map(|m| m.len())
+        .map_err(|e| format!("Failed to read backup: {}", e))?;
+    Ok(BackupInfo {
+        id: backup_id,
+        path: path.to_string_lossy().to_string(),
+        size,
+    })
+}
 
-    // 更新内存中的状态
-    {
-        let mut config_state = state
-            .config
-            .lock()
-            .map_err(|e| format!("Failed to lock config: {}", e))?;
-        *config_state = new_config;
+/// 对比两份备份，返回格式化后 JSON 的 unified diff（内容相同时为空字符串）
+#[tauri::command]
+pub async fn diff_config_backups(backup_a: String, backup_b: String) -> Result<String, String> {
+    let load = |backup_id: &str| -> Result<String, String> {
+        let path = backup_path(backup_id)?;
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read backup {}: {}", backup_id, e))?;
+        let value: Value = serde_json::from_str(crate::config::strip_bom(&content))
+            .map_err(|e| format!("Invalid backup {}: {}", backup_id, e))?;
+        serde_json::to_string_pretty(&value)
+            .map(|pretty| pretty + "\n")
+            .map_err(|e| format!("Failed to format backup {}: {}", backup_id, e))
+    };
+    let old = load(&backup_a)?;
+    let new = load(&backup_b)?;
+    if old == new {
+        return Ok(String::new());
     }
 
+    Ok(similar::TextDiff::from_lines(&old, &new)
+        .unified_diff()
+        .context_radius(3)
+        .header(&backup_a, &backup_b)
+        .to_string())
+}
+
+/// 设置定时备份间隔（秒），传空则关闭定时备份
+#[tauri::command]
+pub async fn set_backup_schedule(interval_secs: Option<u64>) -> Result<(), String> {
+    let mut settings = crate::settings::get_settings();
+    settings.config_backup_interval_secs = interval_secs.filter(|secs| *secs > 0);
+    crate::settings::update_settings(settings)
+}
+
+/// 删除指定的配置备份
+#[tauri::command]
+pub async fn delete_config_backup(backup_id: String) -> Result<(), String> {
+    delete_backup(&backup_id)
+}
+
+/// 清理配置备份，仅保留最新的 keep 份，返回删除的数量
+#[tauri::command]
+pub async fn prune_config_backups(keep: usize) -> Result<usize, String> {
+    prune_backups(keep)
+}
+
+/// 从指定备份恢复配置（恢复前会先备份当前配置）
+#[tauri::command]
+pub async fn restore_config_backup(
+    backup_id: String,
+    state: tauri::State<'_, crate::store::AppState>,
+) -> Result<Value, String> {
+    let path = backup_path(&backup_id)?;
+    if !path.exists() {
+        return Err(format!("Backup not found: {}", backup_id));
+    }
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read backup: {}", e))?;
+
+    let new_backup_id = replace_config(&content, &state)?;
+
     Ok(json!({
         "success": true,
-        "message": "Configuration imported successfully",
-        "backupId": backup_id
+        "message": "Configuration restored successfully",
+        "backupId": new_backup_id
     }))
 }
 
@@ -371,9 +371,27 @@ pub fn run() {
                 if migrated {
                     log::info!("已将副本文件导入到 config.json，并完成归档");
                 }
-                // 确保两个 App 条目存在
+                // 确保各 App 条目存在
                 config_guard.ensure_app(&app_config::AppType::Claude);
                 config_guard.ensure_app(&app_config::AppType::Codex);
+                config_guard.ensure_app(&app_config::AppType::Gemini);
+
+                // 标记旧版本写入 Factory 的 Droid 模型，切换 Provider 时一并替换（只执行一次）
+                if let Some(droid_manager) = config_guard
+                    .droid_manager
+                    .as_mut()
+                    .filter(|m| !m.legacy_models_adopted)
+                {
+                    match droid_config::adopt_legacy_factory_models(&droid_manager.providers) {
+                        Ok(count) => {
+                            if count > 0 {
+                                log::info!("已标记 {} 个旧版 Factory 模型", count);
+                            }
+                            droid_manager.legacy_models_adopted = true;
+                        }
+                        Err(e) => log::warn!("标记旧版 Factory 模型失败: {}", e),
+                    }
+                }
             }
 
             // 保存配置
@@ -402,6 +420,13 @@ pub fn run() {
             // 将同一个实例注入到全局状态，避免重复创建导致的不一致
             app.manage(app_state);
             app.manage(droid2api_service);
+
+            // 按设置恢复上次运行中的 droid2api 服务
+            droid2api_service::restore_instances_on_startup(app.handle());
+            droid2api_service::spawn_supervisor(app.handle());
+
+            // 按设置定时备份配置
+            import_export::spawn_backup_scheduler();
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -424,6 +449,11 @@ pub fn run() {
             commands::read_live_provider_settings,
             commands::get_settings,
             commands::save_settings,
+            commands::diagnose_config_file,
+            commands::save_profile,
+            commands::load_profile,
+            commands::list_profiles,
+            commands::switch_profile,
             commands::check_for_updates,
             commands::is_portable_mode,
             commands::get_claude_plugin_status,
@@ -445,6 +475,8 @@ pub fn run() {
             commands::sync_enabled_mcp_to_codex,
             commands::import_mcp_from_claude,
             commands::import_mcp_from_codex,
+            commands::export_mcp_config,
+            commands::import_mcp_config,
             // ours: endpoint speed test + custom endpoint management
             commands::test_api_endpoints,
             commands::get_custom_endpoints,
@@ -453,20 +485,51 @@ pub fn run() {
             commands::update_endpoint_last_used,
             // Droid configuration management
             commands::get_droid_providers,
+            commands::get_droid_provider_errors,
+            commands::search_droid_providers,
+            commands::list_droid_providers_by_tag,
             commands::get_current_droid_provider,
             commands::add_droid_provider,
             commands::update_droid_provider,
             commands::delete_droid_provider,
+            commands::duplicate_droid_provider,
             commands::switch_droid_provider,
+            commands::switch_droid_provider_safe,
+            commands::switch_all_to_provider,
             commands::fetch_droid_balance,
             commands::fetch_multiple_droid_balances,
+            commands::refresh_droid_balances,
+            commands::check_all_droid_keys,
+            commands::test_droid_provider,
+            commands::delete_droid_session,
+            commands::get_factory_sessions_path,
+            commands::get_factory_config_path_string,
+            commands::find_stale_balances,
             commands::auto_switch_droid_key,
+            commands::simulate_rotation,
+            commands::update_api_key,
+            commands::handle_auth_failure,
+            commands::validate_running_proxy_key,
             commands::get_factory_custom_models,
+            commands::list_factory_models,
+            commands::set_factory_default_model,
+            commands::validate_factory_config,
+            commands::list_factory_backups,
+            commands::restore_factory_backup,
             commands::delete_factory_custom_model,
             commands::update_factory_custom_model,
             // theirs: config import/export and dialogs
             import_export::export_config_to_file,
             import_export::import_config_from_file,
+            import_export::export_config_bundle,
+            import_export::import_config_bundle,
+            import_export::list_config_backups,
+            import_export::create_config_backup,
+            import_export::diff_config_backups,
+            import_export::restore_config_backup,
+            import_export::delete_config_backup,
+            import_export::prune_config_backups,
+            import_export::set_backup_schedule,
             import_export::save_file_dialog,
             import_export::open_file_dialog,
             update_tray_menu,
@@ -474,6 +537,11 @@ pub fn run() {
             droid2api_service::start_droid2api_service,
             droid2api_service::stop_droid2api_service,
             droid2api_service::get_droid2api_service_status,
+            droid2api_service::list_droid2api_instances,
+            droid2api_service::get_droid2api_logs,
+            droid2api_service::set_droid2api_autorestart,
+            droid2api_service::set_droid2api_readiness,
+            droid2api_service::set_node_path,
             droid2api_service::test_droid2api_connection,
         ]);
 
@@ -1,5 +1,6 @@
 use serde_json::{json, Value};
 use std::collections::HashMap;
+use std::path::Path;
 
 use crate::app_config::{AppType, McpConfig, MultiAppConfig};
 
@@ -32,7 +33,25 @@ fn validate_server_spec(spec: &Value) -> Result<(), String> {
     Ok(())
 }
 
-fn validate_mcp_entry(entry: &Value) -> Result<(), String> {
+/// 严格校验：在 `validate_mcp_entry` 基础上要求 args（若存在）为字符串数组
+///
+/// 仅用于写入路径（upsert、`McpConfig::validate`）；读取、快照与导入 live 配置时仍用宽松校验，
+/// 以免已有条目被静默丢弃。
+pub(crate) fn validate_mcp_entry_strict(entry: &Value) -> Result<(), String> {
+    validate_mcp_entry(entry)?;
+    if let Some(args) = entry.get("server").and_then(|server| server.get("args")) {
+        let all_strings = args
+            .as_array()
+            .map(|arr| arr.iter().all(|item| item.is_string()))
+            .unwrap_or(false);
+        if !all_strings {
+            return Err("MCP 服务器 args 必须为字符串数组".into());
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn validate_mcp_entry(entry: &Value) -> Result<(), String> {
     let obj = entry
         .as_object()
         .ok_or_else(|| "MCP 服务器条目必须为 JSON 对象".to_string())?;
@@ -177,17 +196,10 @@ fn extract_server_spec(entry: &Value) -> Result<Value, String> {
 /// 返回已启用的 MCP 服务器（过滤 enabled==true）
 fn collect_enabled_servers(cfg: &McpConfig) -> HashMap<String, Value> {
     let mut out = HashMap::new();
-    for (id, entry) in cfg.servers.iter() {
-        let enabled = entry
-            .get("enabled")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-        if !enabled {
-            continue;
-        }
-        match extract_server_spec(entry) {
+    for id in cfg.enabled_servers() {
+        match extract_server_spec(&cfg.servers[&id]) {
             Ok(spec) => {
-                out.insert(id.clone(), spec);
+                out.insert(id, spec);
             }
             Err(err) => {
                 log::warn!("跳过无效的 MCP 条目 '{}': {}", id, err);
@@ -232,7 +244,6 @@ pub fn upsert_in_config_for(
         return Err("MCP 服务器 ID 不能为空".into());
     }
     normalize_servers_for(config, app);
-    validate_mcp_entry(&spec)?;
 
     let mut entry_obj = spec
         .as_object()
@@ -254,9 +265,17 @@ pub fn upsert_in_config_for(
 
     let value = Value::Object(entry_obj);
 
-    let servers = &mut config.mcp_for_mut(app).servers;
-    let before = servers.get(id).cloned();
-    servers.insert(id.to_string(), value);
+    let mcp = config.mcp_for_mut(app);
+    let before = mcp.servers.insert(id.to_string(), value);
+
+    // 保存前校验该客户端的全部 MCP 配置，失败时撤销本次修改
+    if let Err(errors) = mcp.validate() {
+        match before {
+            Some(previous) => mcp.servers.insert(id.to_string(), previous),
+            None => mcp.servers.remove(id),
+        };
+        return Err(errors.join("\n"));
+    }
 
     Ok(before.is_none())
 }
@@ -285,18 +304,14 @@ pub fn set_enabled_and_sync_for(
         return Err("MCP 服务器 ID 不能为空".into());
     }
     normalize_servers_for(config, app);
-    if let Some(spec) = config.mcp_for_mut(app).servers.get_mut(id) {
-        // 写入 enabled 字段
-        let mut obj = spec
-            .as_object()
-            .cloned()
-            .ok_or_else(|| "MCP 服务器定义必须为 JSON 对象".to_string())?;
-        obj.insert("enabled".into(), json!(enabled));
-        *spec = Value::Object(obj);
-    } else {
+    let mcp = config.mcp_for_mut(app);
+    if !mcp.servers.contains_key(id) {
         // 若不存在则直接返回 false
         return Ok(false);
     }
+    // 保存并同步到客户端前校验该客户端的全部 MCP 配置
+    mcp.validate().map_err(|errors| errors.join("\n"))?;
+    mcp.set_server_enabled(id, enabled)?;
 
     // 同步启用项
     match app {
@@ -308,6 +323,9 @@ pub fn set_enabled_and_sync_for(
             // 将启用项投影到 ~/.codex/config.toml
             sync_enabled_to_codex(config)?;
         }
+        AppType::Gemini => {
+            // Gemini 暂不支持 MCP 投影，仅保存在 config.json
+        }
     }
     Ok(true)
 }
@@ -730,3 +748,79 @@ pub fn sync_enabled_to_codex(config: &MultiAppConfig) -> Result<(), String> {
 
     Ok(())
 }
+
+/// 判断环境变量名或请求头名是否像是敏感信息（API Key、Token 等）
+fn looks_like_secret_name(name: &str) -> bool {
+    let upper = name.to_uppercase();
+    ["KEY", "TOKEN", "SECRET", "PASSWORD", "PASSWD", "CREDENTIAL", "AUTH", "COOKIE"]
+        .iter()
+        .any(|marker| upper.contains(marker))
+}
+
+/// 清空条目中 server.env 与 server.headers（HTTP 类型）下疑似敏感的值（保留键名，便于对方补填）
+fn strip_secret_values(entry: &mut Value) {
+    let Some(server) = entry.get_mut("server") else {
+        return;
+    };
+    for field in ["env", "headers"] {
+        let Some(map) = server.get_mut(field).and_then(|v| v.as_object_mut()) else {
+            continue;
+        };
+        for (name, value) in map.iter_mut() {
+            if looks_like_secret_name(name) {
+                *value = json!("");
+            }
+        }
+    }
+}
+
+/// 将指定客户端的 MCP 配置导出为独立 JSON 文件（不包含供应商配置），返回导出的条目数
+pub fn export_to_file(
+    config: &mut MultiAppConfig,
+    app: &AppType,
+    path: &Path,
+    strip_secrets: bool,
+) -> Result<usize, String> {
+    normalize_servers_for(config, app);
+    let mut exported = config.mcp_for(app).clone();
+    if strip_secrets {
+        for entry in exported.servers.values_mut() {
+            strip_secret_values(entry);
+        }
+    }
+    crate::config::write_json_file(path, &exported)?;
+    Ok(exported.servers.len())
+}
+
+/// 从独立 JSON 文件导入 MCP 配置，返回变更的条目数
+/// - merge=false：整体替换当前客户端的 MCP 配置
+/// - merge=true：按 id 合并；已存在的条目仅在 overwrite=true 时被覆盖
+pub fn import_from_file(
+    config: &mut MultiAppConfig,
+    app: &AppType,
+    path: &Path,
+    merge: bool,
+    overwrite: bool,
+) -> Result<usize, String> {
+    let mut imported: McpConfig = crate::config::read_json_file(path)?;
+    normalize_server_keys(&mut imported.servers);
+    imported.validate().map_err(|errors| errors.join("\n"))?;
+
+    if !merge {
+        let changed = imported.servers.len();
+        *config.mcp_for_mut(app) = imported;
+        return Ok(changed);
+    }
+
+    normalize_servers_for(config, app);
+    let servers = &mut config.mcp_for_mut(app).servers;
+    let mut changed = 0usize;
+    for (id, entry) in imported.servers {
+        if servers.contains_key(&id) && !overwrite {
+            continue;
+        }
+        servers.insert(id, entry);
+        changed += 1;
+    }
+    Ok(changed)
+}
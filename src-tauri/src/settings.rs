@@ -37,6 +37,36 @@ pub struct AppSettings {
     /// Codex 自定义端点列表
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub custom_endpoints_codex: HashMap<String, CustomEndpoint>,
+    /// 启动时自动恢复上次退出前正在运行的 droid2api 服务
+    #[serde(default)]
+    pub restore_droid2api_on_startup: bool,
+    /// droid2api 进程意外退出时自动重启
+    #[serde(default)]
+    pub droid2api_auto_restart: bool,
+    /// 自动重启的最大次数
+    #[serde(default = "default_droid2api_max_restarts")]
+    pub droid2api_max_restarts: u32,
+    /// 自定义 Node.js 可执行文件路径（设置后跳过自动查找）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_path: Option<String>,
+    /// droid2api 启动时探测就绪的路径（未设置时为 /v1/models）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub droid2api_readiness_path: Option<String>,
+    /// 就绪探测的最大次数（未设置时为 10）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub droid2api_readiness_attempts: Option<u32>,
+    /// 就绪探测的间隔（毫秒，未设置时为 500）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub droid2api_readiness_interval_ms: Option<u64>,
+    /// 配置备份最多保留的份数（未设置时为 10，0 表示不限制）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_config_backups: Option<usize>,
+    /// 配置备份目录的总大小上限（字节）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_backup_total_bytes: Option<u64>,
+    /// 定时备份间隔（秒），未设置时不启用
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_backup_interval_secs: Option<u64>,
 }
 
 fn default_show_in_tray() -> bool {
@@ -47,6 +77,10 @@ fn default_minimize_to_tray_on_close() -> bool {
     true
 }
 
+fn default_droid2api_max_restarts() -> u32 {
+    3
+}
+
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
@@ -58,6 +92,16 @@ impl Default for AppSettings {
             language: None,
             custom_endpoints_claude: HashMap::new(),
             custom_endpoints_codex: HashMap::new(),
+            restore_droid2api_on_startup: false,
+            droid2api_auto_restart: false,
+            droid2api_max_restarts: default_droid2api_max_restarts(),
+            node_path: None,
+            droid2api_readiness_path: None,
+            droid2api_readiness_attempts: None,
+            droid2api_readiness_interval_ms: None,
+            max_config_backups: None,
+            max_backup_total_bytes: None,
+            config_backup_interval_secs: None,
         }
     }
 }
@@ -82,6 +126,20 @@ impl AppSettings {
             .filter(|s| !s.is_empty())
             .map(|s| s.to_string());
 
+        self.node_path = self
+            .node_path
+            .as_ref()
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string());
+
+        self.droid2api_readiness_path = self
+            .droid2api_readiness_path
+            .as_ref()
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string());
+
         self.language = self
             .language
             .as_ref()
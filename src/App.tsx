@@ -146,6 +146,18 @@ function App() {
       const currentId = await window.api.getCurrentDroidProvider();
       setDroidProviders(providers);
       setCurrentDroidProviderId(currentId);
+
+      const errors = await window.api.getDroidProviderErrors();
+      if (errors.length > 0) {
+        showNotification(
+          t("notifications.droidProvidersSkipped", {
+            count: errors.length,
+            errors: errors.join("; "),
+          }),
+          "error",
+          6000,
+        );
+      }
     } catch (error) {
       console.error("加载 Droid 配置失败:", error);
     }
@@ -516,7 +528,8 @@ function App() {
 
       {isMcpOpen && (
         <McpPanel
-          appType={activeApp}
+          // Droid 没有独立的 MCP 配置，沿用 Claude 的
+          appType={activeApp === "droid" ? "claude" : activeApp}
           onClose={() => setIsMcpOpen(false)}
           onNotify={showNotification}
         />
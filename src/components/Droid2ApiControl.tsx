@@ -59,7 +59,31 @@ export function Droid2ApiControl({ className = '' }: Droid2ApiControlProps) {
     
     // 定期刷新状态
     const interval = setInterval(refreshStatus, 10000); // 每10秒刷新一次
-    return () => clearInterval(interval);
+
+    // 实例意外退出时立即刷新，不必等下一次轮询
+    let unlisten: (() => void) | undefined;
+    let disposed = false;
+    window.api
+      .onDroid2apiExited((data) => {
+        console.warn(
+          `droid2api 实例 '${data.id}' (端口 ${data.port}) 已退出，退出码: ${data.code ?? '未知'}`,
+        );
+        refreshStatus();
+      })
+      .then((fn) => {
+        if (disposed) {
+          fn();
+        } else {
+          unlisten = fn;
+        }
+      })
+      .catch((error) => console.error('监听 droid2api 退出事件失败:', error));
+
+    return () => {
+      disposed = true;
+      clearInterval(interval);
+      unlisten?.();
+    };
   }, []);
 
   const getStatusColor = () => {
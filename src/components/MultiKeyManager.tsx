@@ -81,7 +81,7 @@ const MultiKeyManager: React.FC<MultiKeyManagerProps> = ({
         id: Date.now().toString(),
         key: trimmedKey,
         name: newKeyData.name || `Key ${apiKeys.length + 1}`,
-        is_active: apiKeys.length === 0,
+        is_active: true,
       };
       onAddKey(newKey);
       setNewKeyData({ name: "", key: "" });
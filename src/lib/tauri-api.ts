@@ -633,6 +633,16 @@ export const tauriAPI = {
     }
   },
 
+  // 获取加载时被跳过的无效 Droid provider 错误信息
+  getDroidProviderErrors: async (): Promise<string[]> => {
+    try {
+      return await invoke("get_droid_provider_errors");
+    } catch (error) {
+      console.error("获取 Droid provider 错误信息失败:", error);
+      return [];
+    }
+  },
+
   // 获取当前 Droid provider ID
   getCurrentDroidProvider: async (): Promise<string> => {
     try {
@@ -757,6 +767,21 @@ export const tauriAPI = {
     });
     return unlisten;
   },
+
+  // 监听 droid2api 实例意外退出事件
+  onDroid2apiExited: async (
+    callback: (data: { id: string; port: number; code: number | null }) => void,
+  ): Promise<UnlistenFn> => {
+    const unlisten = await listen("droid2api://exited", (event) => {
+      try {
+        // 事件 payload 形如 { id: string, port: number, code: number | null }
+        callback(event.payload as any);
+      } catch (e) {
+        console.error("处理 droid2api://exited 事件失败: ", e);
+      }
+    });
+    return unlisten;
+  },
 };
 
 // 创建全局 API 对象，兼容现有代码
@@ -53,6 +53,26 @@ export interface Settings {
   customEndpointsClaude?: Record<string, CustomEndpoint>;
   // Codex 自定义端点列表
   customEndpointsCodex?: Record<string, CustomEndpoint>;
+  // 启动时自动恢复上次运行中的 droid2api 服务
+  restoreDroid2apiOnStartup?: boolean;
+  // droid2api 进程意外退出时自动重启
+  droid2apiAutoRestart?: boolean;
+  // 自动重启的最大次数（默认 3）
+  droid2apiMaxRestarts?: number;
+  // 自定义 Node.js 可执行文件路径（设置后跳过自动查找）
+  nodePath?: string;
+  // droid2api 就绪探测路径（默认 /v1/models）
+  droid2apiReadinessPath?: string;
+  // 就绪探测最大次数（默认 10）
+  droid2apiReadinessAttempts?: number;
+  // 就绪探测间隔（毫秒，默认 500）
+  droid2apiReadinessIntervalMs?: number;
+  // 配置备份最多保留的份数（默认 10，0 表示不限制）
+  maxConfigBackups?: number;
+  // 配置备份目录的总大小上限（字节）
+  maxBackupTotalBytes?: number;
+  // 定时备份间隔（秒），未设置时不启用
+  configBackupIntervalSecs?: number;
 }
 
 // MCP 服务器连接参数（宽松：允许扩展字段）
@@ -107,6 +127,8 @@ export interface DroidCustomModel {
   provider: string;
   max_tokens?: number;
   supports_prompt_caching?: boolean;
+  // 由 cc-switch 写入的模型带有此标记
+  cc_switch_managed?: boolean;
 }
 
 // Droid 配置（对应 .factory/config.json）
@@ -123,8 +145,9 @@ export interface ApiKeyInfo {
   id: string;
   key: string;
   name?: string; // 可选的标识名称
-  is_active: boolean; // 当前是否在使用
+  is_active: boolean; // 是否启用（停用的 Key 不参与自动切换）
   last_used?: number; // 最后使用时间
+  request_count?: number; // 被选为当前 Key 的次数
   balance?: {
     total_allowance: number;
     total_used: number;
@@ -151,4 +174,5 @@ export interface DroidProvider {
   max_tokens?: number;
   supports_prompt_caching?: boolean;
   createdAt?: number;
+  tags?: string[]; // 分组标签
 }
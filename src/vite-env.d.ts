@@ -57,6 +57,9 @@ declare global {
       onProviderSwitched: (
         callback: (data: { appType: string; providerId: string }) => void,
       ) => Promise<UnlistenFn>;
+      onDroid2apiExited: (
+        callback: (data: { id: string; port: number; code: number | null }) => void,
+      ) => Promise<UnlistenFn>;
       getSettings: () => Promise<Settings>;
       saveSettings: (settings: Settings) => Promise<boolean>;
       checkForUpdates: () => Promise<void>;
@@ -137,6 +140,7 @@ declare global {
       ) => Promise<void>;
       // Droid 配置管理
       getDroidProviders: () => Promise<DroidProvider[]>;
+      getDroidProviderErrors: () => Promise<string[]>;
       getCurrentDroidProvider: () => Promise<string>;
       addDroidProvider: (provider: DroidProvider) => Promise<boolean>;
       updateDroidProvider: (provider: DroidProvider) => Promise<boolean>;